@@ -0,0 +1,47 @@
+//! Compares reading a large output through buffers of different capacities.
+//!
+//! `cargo bench --bench stream_capacity`
+
+#[cfg(feature = "sync")]
+fn main() {
+    use ptyprocess::PtyProcessBuilder;
+    use std::{io::BufRead, process::Command, time::Instant};
+
+    const SIZE: usize = 100 * 1024 * 1024;
+
+    for &capacity in &[4 * 1024, 64 * 1024] {
+        let mut command = Command::new("head");
+        command.args(["-c", &SIZE.to_string(), "/dev/zero"]);
+        let mut process = PtyProcessBuilder::new()
+            .command(command)
+            .stream_capacity(capacity)
+            .build()
+            .unwrap();
+
+        // go through the internal buffer, as bigger reads bypass it
+        let start = Instant::now();
+        let mut read = 0;
+        loop {
+            let n = process.fill_buf().unwrap().len();
+            if n == 0 {
+                break;
+            }
+
+            process.consume(n);
+            read += n;
+        }
+
+        assert_eq!(read, SIZE);
+        println!(
+            "capacity {:>6} bytes: read {} MiB in {:?}",
+            capacity,
+            SIZE / 1024 / 1024,
+            start.elapsed()
+        );
+    }
+}
+
+#[cfg(feature = "async")]
+fn main() {
+    todo!("Use a sync version for this benchmark");
+}
@@ -1,5 +1,5 @@
-/// To run an example run the following command
-/// `cargo run --example cat`.
+//! To run an example run the following command
+//! `cargo run --example cat`.
 
 #[cfg(feature = "sync")]
 fn main() {
@@ -0,0 +1,63 @@
+/// AnsiSequence represents common ANSI escape sequences [wiki].
+///
+/// Positions are 1-based as they are in the sequences themselves.
+///
+/// [wiki]: https://en.wikipedia.org/wiki/ANSI_escape_code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AnsiSequence {
+    /// Clears an entire screen `ESC [ 2 J`.
+    ClearScreen,
+    /// Clears an entire line `ESC [ 2 K`.
+    ClearLine,
+    /// Moves a cursor to the top left corner `ESC [ H`.
+    CursorHome,
+    /// Moves a cursor to a given row and column `ESC [ row ; col H`.
+    CursorTo(u16, u16),
+    /// Moves a cursor up by a given number of cells `ESC [ n A`.
+    CursorUp(u16),
+    /// Moves a cursor down by a given number of cells `ESC [ n B`.
+    CursorDown(u16),
+    /// Moves a cursor forward by a given number of cells `ESC [ n C`.
+    CursorForward(u16),
+    /// Moves a cursor back by a given number of cells `ESC [ n D`.
+    CursorBack(u16),
+    /// Saves a cursor position `ESC 7`.
+    SaveCursor,
+    /// Restores a saved cursor position `ESC 8`.
+    RestoreCursor,
+    /// Resets all graphic attributes (colors, bold, etc.) `ESC [ 0 m`.
+    ResetAttributes,
+}
+
+impl AnsiSequence {
+    /// Returns the bytes of a sequence.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::ClearScreen => b"\x1b[2J".to_vec(),
+            Self::ClearLine => b"\x1b[2K".to_vec(),
+            Self::CursorHome => b"\x1b[H".to_vec(),
+            Self::CursorTo(row, col) => format!("\x1b[{};{}H", row, col).into_bytes(),
+            Self::CursorUp(n) => format!("\x1b[{}A", n).into_bytes(),
+            Self::CursorDown(n) => format!("\x1b[{}B", n).into_bytes(),
+            Self::CursorForward(n) => format!("\x1b[{}C", n).into_bytes(),
+            Self::CursorBack(n) => format!("\x1b[{}D", n).into_bytes(),
+            Self::SaveCursor => b"\x1b7".to_vec(),
+            Self::RestoreCursor => b"\x1b8".to_vec(),
+            Self::ResetAttributes => b"\x1b[0m".to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn to_bytes() {
+        assert_eq!(AnsiSequence::ClearScreen.to_bytes(), b"\x1b[2J");
+        assert_eq!(AnsiSequence::CursorTo(3, 12).to_bytes(), b"\x1b[3;12H");
+        assert_eq!(AnsiSequence::CursorBack(1).to_bytes(), b"\x1b[1D");
+        assert_eq!(AnsiSequence::RestoreCursor.to_bytes(), b"\x1b8");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::process::PtyProcess;
+use std::ops::{Deref, DerefMut};
+
+/// BufferedPtyProcess is a [PtyProcess] which is read through a buffer,
+/// created by [PtyProcess::buffered].
+///
+/// It implements buffered reading traits (`BufRead` or `AsyncBufRead`),
+/// so small reads (e.g byte by byte) are served from the buffer instead of a system call each.
+/// The buffer is the one of the process' stream,
+/// its capacity is set by [crate::PtyProcessBuilder::stream_capacity] (8 KiB by default).
+///
+/// Non-blocking reads like `try_read` return buffered data first
+/// and return `None` only if the buffer is empty and there's nothing to read from a PTY.
+///
+/// All other operations are available through [Deref] to a [PtyProcess].
+#[derive(Debug)]
+pub struct BufferedPtyProcess {
+    process: PtyProcess,
+}
+
+impl BufferedPtyProcess {
+    pub(crate) fn new(process: PtyProcess) -> Self {
+        Self { process }
+    }
+
+    /// Returns a wrapped process.
+    ///
+    /// Data which is already buffered stays available to the process.
+    pub fn into_inner(self) -> PtyProcess {
+        self.process
+    }
+}
+
+impl Deref for BufferedPtyProcess {
+    type Target = PtyProcess;
+
+    fn deref(&self) -> &Self::Target {
+        &self.process
+    }
+}
+
+impl DerefMut for BufferedPtyProcess {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.process
+    }
+}
+
+#[cfg(feature = "sync")]
+mod sync_buffered {
+    use super::BufferedPtyProcess;
+    use std::io::{self, BufRead, Read, Write};
+
+    impl Read for BufferedPtyProcess {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.process.read(buf)
+        }
+    }
+
+    impl BufRead for BufferedPtyProcess {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            self.process.fill_buf()
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.process.consume(amt)
+        }
+    }
+
+    impl Write for BufferedPtyProcess {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.process.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.process.flush()
+        }
+
+        fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+            self.process.write_vectored(bufs)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_buffered {
+    use super::BufferedPtyProcess;
+    use futures_lite::{AsyncBufRead, AsyncRead, AsyncWrite};
+    use std::{
+        io,
+        ops::DerefMut,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    impl AsyncRead for BufferedPtyProcess {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(self.process.deref_mut()).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncBufRead for BufferedPtyProcess {
+        fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+            Pin::new(self.get_mut().process.deref_mut()).poll_fill_buf(cx)
+        }
+
+        fn consume(mut self: Pin<&mut Self>, amt: usize) {
+            Pin::new(self.process.deref_mut()).consume(amt)
+        }
+    }
+
+    impl AsyncWrite for BufferedPtyProcess {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(self.process.deref_mut()).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(self.process.deref_mut()).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(self.process.deref_mut()).poll_close(cx)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(self.process.deref_mut()).poll_write_vectored(cx, bufs)
+        }
+    }
+}
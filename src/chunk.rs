@@ -0,0 +1,222 @@
+use crate::split::PtyReader;
+use crate::stream::has_reached_end_of_sdtout;
+use std::io;
+
+const READ_SIZE: usize = 4096;
+// An incomplete sequence longer than this is most likely garbage,
+// so it's returned as it is instead of being buffered forever.
+const MAX_SEQUENCE_LEN: usize = 1024;
+
+const ESC: u8 = 0x1b;
+
+/// ChunkReader reads a child's output by chunks which are safe to be parsed separately.
+///
+/// A chunk never ends in the middle of a multi-byte UTF-8 sequence
+/// or an escape sequence (e.g `CSI` like `\x1b[31m`),
+/// an incomplete sequence is kept internally until the rest of it is read.
+/// So a terminal parser doesn't have to handle sequences spanning read boundaries.
+///
+/// Only `CSI` and short escape sequences (e.g `\x1b(B`) are recognized,
+/// string sequences like `OSC` may still be split.
+/// Invalid sequences are passed through as they are.
+///
+/// It's based on a non-blocking [PtyReader::try_read].
+///
+/// ```no_run
+/// use ptyprocess::{ChunkReader, PtyProcess};
+/// use std::process::Command;
+///
+/// let mut process = PtyProcess::spawn(Command::new("ls")).unwrap();
+/// let stdout = process.take_stdout().unwrap().unwrap();
+/// let mut reader = ChunkReader::new(stdout);
+///
+/// while !reader.is_eof() {
+///     if let Some(chunk) = reader.next_chunk().unwrap() {
+///         // parse the chunk
+///     }
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ChunkReader {
+    reader: PtyReader,
+    buf: Vec<u8>,
+    // a length of a chunk returned by a previous call
+    returned: usize,
+    eof: bool,
+}
+
+impl ChunkReader {
+    /// Creates a reader on top of a [PtyReader].
+    pub fn new(reader: PtyReader) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            returned: 0,
+            eof: false,
+        }
+    }
+
+    /// Returns a next complete chunk without blocking.
+    ///
+    /// It returns:
+    ///     - Ok(Some(chunk)) a non empty chunk.
+    ///     - Ok(None) if there's nothing complete to return at the moment or `EOF` is reached.
+    ///     - Err(err) an IO error which occured.
+    ///
+    /// Once `EOF` is reached an incomplete sequence left is returned as the last chunk.
+    pub fn next_chunk(&mut self) -> io::Result<Option<&[u8]>> {
+        self.buf.drain(..self.returned);
+        self.returned = 0;
+
+        let len = loop {
+            let len = match complete_len(&self.buf) {
+                0 if self.eof || self.buf.len() > MAX_SEQUENCE_LEN => self.buf.len(),
+                len => len,
+            };
+
+            if len > 0 || self.eof {
+                break len;
+            }
+
+            if !self.fill()? {
+                return Ok(None);
+            }
+        };
+
+        if len == 0 {
+            return Ok(None);
+        }
+
+        self.returned = len;
+        Ok(Some(&self.buf[..len]))
+    }
+
+    /// Checks whether `EOF` is reached and all chunks are returned.
+    pub fn is_eof(&self) -> bool {
+        self.eof && self.buf.len() == self.returned
+    }
+
+    /// Returns an inner reader.
+    ///
+    /// Buffered data which were not returned yet are lost.
+    pub fn into_inner(self) -> PtyReader {
+        self.reader
+    }
+
+    // Reads more data to a buffer.
+    // It returns false if there's nothing to read at the moment.
+    fn fill(&mut self) -> io::Result<bool> {
+        let len = self.buf.len();
+        self.buf.resize(len + READ_SIZE, 0);
+
+        let (n, more) = match self.reader.try_read(&mut self.buf[len..]) {
+            Ok(Some(0)) => {
+                self.eof = true;
+                (0, true)
+            }
+            Ok(Some(n)) => (n, true),
+            Ok(None) => (0, false),
+            Err(ref err) if has_reached_end_of_sdtout(err) => {
+                self.eof = true;
+                (0, true)
+            }
+            Err(err) => {
+                self.buf.truncate(len);
+                return Err(err);
+            }
+        };
+        self.buf.truncate(len + n);
+
+        Ok(more)
+    }
+}
+
+// Returns a length of the longest prefix which doesn't end in the middle of a sequence.
+fn complete_len(data: &[u8]) -> usize {
+    let mut i = 0;
+    while i < data.len() {
+        match sequence_len(&data[i..]) {
+            Some(len) => i += len,
+            None => break,
+        }
+    }
+
+    i
+}
+
+// Returns a length of a sequence at the start of data or None if it's incomplete.
+// An invalid sequence ends right before an unexpected byte.
+fn sequence_len(data: &[u8]) -> Option<usize> {
+    let expected = match data[0] {
+        ESC => return escape_len(data),
+        0xc2..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf4 => 4,
+        _ => return Some(1),
+    };
+
+    for i in 1..expected {
+        match data.get(i) {
+            Some(0x80..=0xbf) => {}
+            Some(_) => return Some(i),
+            None => return None,
+        }
+    }
+
+    Some(expected)
+}
+
+fn escape_len(data: &[u8]) -> Option<usize> {
+    // A CSI is followed by parameter and intermediate bytes and ends by a final byte,
+    // other sequences may have only intermediate bytes.
+    let (start, params, last) = match data.get(1)? {
+        b'[' => (2, 0x20..=0x3f, 0x40..=0x7e),
+        0x20..=0x2f => (2, 0x20..=0x2f, 0x30..=0x7e),
+        _ => return Some(2),
+    };
+
+    for (i, b) in data.iter().enumerate().skip(start) {
+        if params.contains(b) {
+            continue;
+        }
+
+        return match last.contains(b) {
+            true => Some(i + 1),
+            false => Some(i),
+        };
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn complete() {
+        assert_eq!(complete_len(b""), 0);
+        assert_eq!(complete_len(b"hello"), 5);
+        assert_eq!(complete_len(b"a\x1b[31mb"), 7);
+        assert_eq!(complete_len(b"a\x1b[1;31"), 1);
+        assert_eq!(complete_len(b"a\x1b["), 1);
+        assert_eq!(complete_len(b"a\x1b"), 1);
+        assert_eq!(complete_len(b"a\x1b(B"), 4);
+        assert_eq!(complete_len(b"a\x1b("), 1);
+        assert_eq!(complete_len(b"a\x1b7"), 3);
+        assert_eq!(complete_len("aé".as_bytes()), 3);
+        assert_eq!(complete_len(&"aé".as_bytes()[..2]), 1);
+        assert_eq!(complete_len(&"a€".as_bytes()[..3]), 1);
+        assert_eq!(complete_len(&"a😀".as_bytes()[..4]), 1);
+    }
+
+    #[test]
+    fn invalid() {
+        // a control character interrupts a CSI
+        assert_eq!(complete_len(b"\x1b[3\n"), 4);
+        // a lone continuation byte and an unexpected lead byte
+        assert_eq!(complete_len(b"\x80a\xffb"), 4);
+        // a lead byte followed by a non continuation byte
+        assert_eq!(complete_len(b"\xc3a"), 2);
+    }
+}
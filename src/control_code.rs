@@ -162,6 +162,51 @@ impl From<ControlCode> for u8 {
     }
 }
 
+impl TryFrom<u8> for ControlCode {
+    type Error = ();
+
+    fn try_from(b: u8) -> Result<ControlCode, ()> {
+        use ControlCode::*;
+        match b {
+            0 => Ok(Null),
+            1 => Ok(StartOfHeading),
+            2 => Ok(StartOfText),
+            3 => Ok(EndOfText),
+            4 => Ok(EndOfTransmission),
+            5 => Ok(Enquiry),
+            6 => Ok(Acknowledge),
+            7 => Ok(Bell),
+            8 => Ok(Backspace),
+            9 => Ok(HorizontalTabulation),
+            10 => Ok(LineFeed),
+            11 => Ok(VerticalTabulation),
+            12 => Ok(FormFeed),
+            13 => Ok(CarriageReturn),
+            14 => Ok(ShiftOut),
+            15 => Ok(ShiftIn),
+            16 => Ok(DataLinkEscape),
+            17 => Ok(DeviceControl1),
+            18 => Ok(DeviceControl2),
+            19 => Ok(DeviceControl3),
+            20 => Ok(DeviceControl4),
+            21 => Ok(NegativeAcknowledge),
+            22 => Ok(SynchronousIdle),
+            23 => Ok(EndOfTransmissionBlock),
+            24 => Ok(Cancel),
+            25 => Ok(EndOfMedium),
+            26 => Ok(Substitute),
+            27 => Ok(Escape),
+            28 => Ok(FileSeparator),
+            29 => Ok(GroupSeparator),
+            30 => Ok(RecordSeparator),
+            31 => Ok(UnitSeparator),
+            32 => Ok(Space),
+            127 => Ok(Delete),
+            _ => Err(()),
+        }
+    }
+}
+
 impl TryFrom<char> for ControlCode {
     type Error = ();
 
@@ -0,0 +1,226 @@
+use nix::errno::Errno;
+use nix::sys::wait::WaitStatus;
+use std::{error, fmt, io};
+
+/// A result type used by the library.
+pub type Result<T> = std::result::Result<T, PtyError>;
+
+/// PtyError represents a failure of a PTY or a child process operation.
+#[derive(Debug)]
+pub enum PtyError {
+    /// A child process could not be spawned,
+    /// because a PTY could not be set up or a fork failed.
+    Spawn(io::Error),
+    /// A child process failed to set itself up before a command is executed.
+    ///
+    /// A stage tells which step failed.
+    ChildSetup(SpawnStage, io::Error),
+    /// A command could not be executed in a child process.
+    Exec(io::Error),
+    /// A command could not be executed as it's not found.
+    CommandNotFound,
+    /// A terminal size could not be read or set.
+    Winsize(io::Error),
+    /// A child process doesn't exist anymore,
+    /// it's already terminated and reaped.
+    Terminated,
+    /// A child process changed its state in an unexpected way (e.g was stopped).
+    UnexpectedStatus(WaitStatus),
+    /// Any other IO error.
+    Io(io::Error),
+}
+
+impl PtyError {
+    /// Returns an OS error code if the error was caused by a system call.
+    pub fn raw_os_error(&self) -> Option<i32> {
+        match self {
+            Self::Spawn(err)
+            | Self::ChildSetup(_, err)
+            | Self::Exec(err)
+            | Self::Winsize(err)
+            | Self::Io(err) => err.raw_os_error(),
+            Self::CommandNotFound => Some(Errno::ENOENT as i32),
+            Self::Terminated | Self::UnexpectedStatus(_) => None,
+        }
+    }
+
+    pub(crate) fn exec(err: io::Error) -> Self {
+        match err.kind() {
+            io::ErrorKind::NotFound => Self::CommandNotFound,
+            _ => Self::Exec(err),
+        }
+    }
+
+    pub(crate) fn spawn(err: nix::Error) -> Self {
+        Self::Spawn(nix_error_to_io(err))
+    }
+
+    pub(crate) fn winsize(err: nix::Error) -> Self {
+        Self::Winsize(nix_error_to_io(err))
+    }
+}
+
+/// SpawnStage is a step of a child process set up at which it failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpawnStage {
+    /// Resetting signal dispositions and a signal mask.
+    ResetSignals,
+    /// Opening a PTY slave.
+    OpenSlave,
+    /// Creating a new session and making the PTY a controlling terminal.
+    ControllingTty,
+    /// Moving a child to a process group.
+    ProcessGroup,
+    /// Redirecting standard streams to the PTY.
+    RedirectStreams,
+    /// Applying initial terminal settings (echo and a window size).
+    TerminalSettings,
+    /// Changing a working directory.
+    WorkingDirectory,
+    /// Preparing a pipe used to report errors to a parent.
+    ErrorPipe,
+    /// Closing inherited descriptors.
+    CloseFds,
+}
+
+impl SpawnStage {
+    const ALL: [Self; 9] = [
+        Self::ResetSignals,
+        Self::OpenSlave,
+        Self::ControllingTty,
+        Self::ProcessGroup,
+        Self::RedirectStreams,
+        Self::TerminalSettings,
+        Self::WorkingDirectory,
+        Self::ErrorPipe,
+        Self::CloseFds,
+    ];
+
+    /// Returns a non zero tag used to send a stage through a pipe.
+    pub(crate) fn tag(self) -> u8 {
+        Self::ALL.iter().position(|&stage| stage == self).unwrap() as u8 + 1
+    }
+
+    pub(crate) fn from_tag(tag: u8) -> Option<Self> {
+        Self::ALL.get((tag as usize).checked_sub(1)?).copied()
+    }
+}
+
+impl fmt::Display for SpawnStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let stage = match self {
+            Self::ResetSignals => "resetting signals",
+            Self::OpenSlave => "opening a PTY slave",
+            Self::ControllingTty => "setting a controlling terminal",
+            Self::ProcessGroup => "setting a process group",
+            Self::RedirectStreams => "redirecting standard streams",
+            Self::TerminalSettings => "applying terminal settings",
+            Self::WorkingDirectory => "changing a working directory",
+            Self::ErrorPipe => "preparing an error pipe",
+            Self::CloseFds => "closing inherited descriptors",
+        };
+
+        f.write_str(stage)
+    }
+}
+
+impl fmt::Display for PtyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(err) => write!(f, "failed to spawn a process: {}", err),
+            Self::ChildSetup(stage, err) => {
+                write!(f, "failed to set up a child process ({}): {}", stage, err)
+            }
+            Self::Exec(err) => write!(f, "failed to execute a command: {}", err),
+            Self::CommandNotFound => write!(f, "a command is not found"),
+            Self::Winsize(err) => write!(f, "failed to access a window size: {}", err),
+            Self::Terminated => write!(f, "a process is already terminated"),
+            Self::UnexpectedStatus(status) => write!(f, "unexpected process status {:?}", status),
+            Self::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl error::Error for PtyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Self::Spawn(err)
+            | Self::ChildSetup(_, err)
+            | Self::Exec(err)
+            | Self::Winsize(err)
+            | Self::Io(err) => Some(err),
+            Self::CommandNotFound | Self::Terminated | Self::UnexpectedStatus(_) => None,
+        }
+    }
+}
+
+impl From<nix::Error> for PtyError {
+    fn from(err: nix::Error) -> Self {
+        match err {
+            // waitpid(2) and kill(2) return these when there's no such a child.
+            nix::Error::Sys(Errno::ECHILD) | nix::Error::Sys(Errno::ESRCH) => Self::Terminated,
+            err => Self::Io(nix_error_to_io(err)),
+        }
+    }
+}
+
+impl From<io::Error> for PtyError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<PtyError> for io::Error {
+    fn from(err: PtyError) -> Self {
+        match err {
+            PtyError::Spawn(err)
+            | PtyError::ChildSetup(_, err)
+            | PtyError::Exec(err)
+            | PtyError::Winsize(err)
+            | PtyError::Io(err) => err,
+            PtyError::CommandNotFound => io::Error::from_raw_os_error(Errno::ENOENT as i32),
+            PtyError::Terminated => io::Error::from_raw_os_error(Errno::ECHILD as i32),
+            err => io::Error::new(io::ErrorKind::Other, err.to_string()),
+        }
+    }
+}
+
+pub(crate) fn nix_error_to_io(err: nix::Error) -> io::Error {
+    match err.as_errno() {
+        Some(code) => io::Error::from_raw_os_error(code as _),
+        None => io::Error::new(
+            io::ErrorKind::Other,
+            "Unexpected error type conversion from nix to io",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_stage_tag() {
+        for stage in SpawnStage::ALL.iter().copied() {
+            assert_ne!(stage.tag(), 0);
+            assert_eq!(SpawnStage::from_tag(stage.tag()), Some(stage));
+        }
+
+        assert_eq!(SpawnStage::from_tag(0), None);
+        assert_eq!(SpawnStage::from_tag(u8::MAX), None);
+    }
+
+    #[test]
+    fn child_setup_error() {
+        let err = PtyError::ChildSetup(
+            SpawnStage::ControllingTty,
+            io::Error::from_raw_os_error(Errno::ENXIO as i32),
+        );
+
+        assert_eq!(err.raw_os_error(), Some(Errno::ENXIO as i32));
+        assert!(err
+            .to_string()
+            .starts_with("failed to set up a child process (setting a controlling terminal)"));
+    }
+}
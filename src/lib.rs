@@ -48,13 +48,40 @@
 //! process.send_line("hello cat").await.expect("failed writing");
 //! ```
 
+// `io::Error::other` is only stable since Rust 1.74
+#![allow(clippy::io_other_error)]
+
+mod ansi;
+mod buffered;
+#[cfg(feature = "sync")]
+mod chunk;
 mod control_code;
+mod error;
+mod line_ending;
 mod process;
+mod scrollback;
+#[cfg(feature = "sigchld")]
+mod sigchld;
+mod split;
+mod status;
 mod stream;
+#[cfg(all(feature = "utmp", target_os = "linux"))]
+mod utmp;
 
+pub use crate::ansi::AnsiSequence;
+pub use crate::buffered::BufferedPtyProcess;
+#[cfg(feature = "sync")]
+pub use crate::chunk::ChunkReader;
 pub use crate::control_code::ControlCode;
-pub use crate::process::PtyProcess;
+pub use crate::error::{PtyError, Result, SpawnStage};
+pub use crate::line_ending::LineEnding;
+pub use crate::process::{Pty, PtyProcess, PtyProcessBuilder, RawModeGuard, SpawnEvent};
+pub use crate::scrollback::ScrollbackReader;
+#[cfg(feature = "sigchld")]
+pub use crate::sigchld::SigchldReaper;
+pub use crate::split::{PtyReader, PtyWriter};
+pub use crate::status::{describe_status, PtyExitStatus};
 
 pub use nix::sys::signal::Signal;
+pub use nix::sys::termios;
 pub use nix::sys::wait::WaitStatus;
-pub use nix::Error;
@@ -0,0 +1,35 @@
+/// LineEnding represents a sequence of characters which terminates a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    /// Line feed `\n`, used on Unix.
+    Lf,
+    /// Carriage return followed by line feed `\r\n`, used on Windows and in many network protocols.
+    CrLf,
+    /// Carriage return `\r`, which is what the Enter key produces in a terminal.
+    Cr,
+}
+
+impl LineEnding {
+    /// Returns the bytes of a line ending.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            Self::Lf => b"\n",
+            Self::CrLf => b"\r\n",
+            Self::Cr => b"\r",
+        }
+    }
+}
+
+impl Default for LineEnding {
+    /// Returns a platform default line ending.
+    fn default() -> Self {
+        #[cfg(windows)]
+        {
+            Self::CrLf
+        }
+        #[cfg(not(windows))]
+        {
+            Self::Lf
+        }
+    }
+}
@@ -1,26 +1,44 @@
+use crate::ansi::AnsiSequence;
+use crate::buffered::BufferedPtyProcess;
 use crate::control_code::ControlCode;
-use crate::stream::Stream;
+use crate::error::{nix_error_to_io, PtyError, Result, SpawnStage};
+use crate::line_ending::LineEnding;
+use crate::split::{PtyReader, PtyWriter};
+use crate::status::PtyExitStatus;
+#[cfg(feature = "tokio")]
+use crate::stream::TokioStream;
+use crate::stream::{self, Stream};
+#[cfg(all(feature = "utmp", target_os = "linux"))]
+use crate::utmp::UtmpEntry;
 #[cfg(feature = "async")]
-use futures_lite::AsyncWriteExt;
-use nix::errno::{self, Errno};
+use futures_lite::{future, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use nix::errno::Errno;
 use nix::fcntl::{fcntl, open, FcntlArg, FdFlag, OFlag};
 use nix::libc::{self, winsize, STDERR_FILENO, STDIN_FILENO, STDOUT_FILENO};
+use nix::poll::{poll, PollFd, PollFlags};
 use nix::pty::PtyMaster;
 use nix::pty::{grantpt, posix_openpt, unlockpt};
 use nix::sys::stat::Mode;
 use nix::sys::wait::{self, waitpid, WaitStatus};
 use nix::sys::{signal, termios};
 use nix::unistd::{
-    self, close, dup, dup2, fork, isatty, pipe, setsid, sysconf, write, ForkResult, Pid, SysconfVar,
+    self, close, dup, dup2, fork, isatty, pipe, setpgid, setsid, sysconf, tcgetpgrp, write,
+    ForkResult, Pid, SysconfVar,
 };
-use nix::{ioctl_write_ptr_bad, Error, Result};
+use nix::{ioctl_none_bad, ioctl_write_ptr_bad, Error};
 use signal::Signal::SIGKILL;
 use std::convert::TryInto;
 use std::fs::File;
 use std::io::Write;
+#[cfg(feature = "sync")]
+use std::io::{BufRead, Read};
 use std::ops::{Deref, DerefMut};
+use std::os::unix::io::{AsFd, BorrowedFd, OwnedFd};
 use std::os::unix::prelude::{AsRawFd, CommandExt, FromRawFd, RawFd};
+use std::panic;
+use std::path::{Path, PathBuf};
 use std::process::{self, Command};
+use std::sync::{Arc, Mutex};
 use std::time::{self, Duration};
 use std::{io, thread};
 use termios::SpecialCharacterIndices;
@@ -29,6 +47,33 @@ const DEFAULT_TERM_COLS: u16 = 80;
 const DEFAULT_TERM_ROWS: u16 = 24;
 const DEFAULT_VEOF_CHAR: u8 = 0x4; // ^D
 const DEFAULT_INTR_CHAR: u8 = 0x3; // ^C
+const DEFAULT_VSTART_CHAR: u8 = 0x11; // ^Q
+const DEFAULT_VSTOP_CHAR: u8 = 0x13; // ^S
+const DEFAULT_VQUIT_CHAR: u8 = 0x1c; // ^\
+const DEFAULT_VSUSP_CHAR: u8 = 0x1a; // ^Z
+const DEFAULT_SHELL: &str = "/bin/sh";
+const DEFAULT_TERM: &str = "xterm-256color";
+// Variables kept by `spawn_clean_env`.
+const CLEAN_ENV_VARS: [&str; 9] = [
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_ALL", "LC_CTYPE", "TMPDIR",
+];
+const DEFAULT_TERMINATE_APPROACH_DELAY: Duration = Duration::from_millis(100);
+const DEFAULT_INTERACT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+// Signals sent one by one by `exit` before resorting to `SIGKILL`.
+const EXIT_SIGNALS: [signal::Signal; 4] = [
+    signal::SIGHUP,
+    signal::SIGCONT,
+    signal::SIGINT,
+    signal::SIGTERM,
+];
+
+// A stage at which a child failed is sent to a parent along with errno.
+// Set up stages are tagged by [SpawnStage::tag] which is never 0.
+const CHILD_STAGE_EXEC: u8 = 0;
+
+// An exit code of a child spawned by [PtyProcess::spawn_fn] which closure panicked,
+// it's the same code a Rust program exits with on a panic.
+const SPAWN_FN_PANIC_EXIT_CODE: i32 = 101;
 
 /// PtyProcess controls a spawned process and communication with this.
 ///
@@ -44,6 +89,19 @@ const DEFAULT_INTR_CHAR: u8 = 0x3; // ^C
 /// process.write_all(b"Hello World").unwrap();
 /// process.flush().unwrap();
 /// ```
+///
+/// Reads through `&mut PtyProcess` go through a buffer of an underlying stream
+/// (8 KiB unless [PtyProcessBuilder::stream_capacity] is set),
+/// so reading by small portions doesn't issue a system call for each read.
+/// [PtyProcess::buffered] wraps a process into a type implementing buffered reading traits.
+///
+/// In a sync version [std::io::Read] and [std::io::Write] are also implemented for `&PtyProcess`,
+/// so one thread may read from a child while another one writes to it.
+/// Reads through `&PtyProcess` bypass the buffer,
+/// so mixing them with buffered reads may return data out of order.
+///
+/// Once dropped a child is terminated by [PtyProcess::exit] which blocks a thread,
+/// so in an async version `PtyProcess::shutdown` is better to be called instead.
 #[derive(Debug)]
 pub struct PtyProcess {
     master: Master,
@@ -52,6 +110,24 @@ pub struct PtyProcess {
     eof_char: u8,
     intr_char: u8,
     terminate_approach_delay: Duration,
+    interact_poll_interval: Duration,
+    // shared with duplicates so a child reaped by any of them is known to all
+    exit_status: Arc<Mutex<Option<WaitStatus>>>,
+    detached: bool,
+    stdin_taken: bool,
+    stdout_taken: bool,
+    stream_capacity: usize,
+    stderr: Option<File>,
+    // terminal attributes right after a spawn,
+    // `Termios` is not `Sync` on its own
+    initial_termios: Mutex<termios::Termios>,
+    // an incomplete UTF-8 sequence left by `read_available_string`
+    #[cfg(feature = "sync")]
+    utf8_tail: Vec<u8>,
+    #[cfg(feature = "tokio")]
+    tokio_stream: Option<TokioStream>,
+    #[cfg(all(feature = "utmp", target_os = "linux"))]
+    utmp_entry: Mutex<Option<UtmpEntry>>,
 }
 
 impl PtyProcess {
@@ -62,84 +138,289 @@ impl PtyProcess {
     ///   # use ptyprocess::PtyProcess;
     ///     let proc = PtyProcess::spawn(Command::new("bash"));
     /// ```
-    pub fn spawn(mut command: Command) -> Result<Self> {
-        let eof_char = get_eof_char();
-        let intr_char = get_intr_char();
+    pub fn spawn(command: Command) -> Result<Self> {
+        Self::spawn_with_size(command, DEFAULT_TERM_COLS, DEFAULT_TERM_ROWS)
+    }
 
-        let master = Master::open()?;
-        master.grant_slave_access()?;
-        master.unlock_slave()?;
+    /// Spawns a child process with a given terminal size and create a [PtyProcess].
+    ///
+    /// The size is set before a command is executed,
+    /// so the child observes it right from the start.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_with_size(Command::new("vim"), 120, 40);
+    /// ```
+    pub fn spawn_with_size(command: Command, cols: u16, rows: u16) -> Result<Self> {
+        PtyProcessBuilder::new()
+            .command(command)
+            .window_size(cols, rows)
+            .build()
+    }
 
-        // handle errors in child executions by pipe
-        let (exec_err_pipe_read, exec_err_pipe_write) = pipe()?;
+    /// Spawns a child process with a given echo setting and create a [PtyProcess].
+    ///
+    /// By default [PtyProcess::spawn] turns echo off.
+    /// The setting is applied before a command is executed.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_with_echo(Command::new("bash"), true);
+    /// ```
+    pub fn spawn_with_echo(command: Command, on: bool) -> Result<Self> {
+        PtyProcessBuilder::new().command(command).echo(on).build()
+    }
 
-        let fork = unsafe { fork()? };
-        match fork {
-            ForkResult::Child => {
-                let err = || -> Result<()> {
-                    let device = master.get_slave_name()?;
-                    let slave_fd = master.get_slave_fd()?;
-                    drop(master);
+    /// Spawns an interactive user's shell and create a [PtyProcess].
+    ///
+    /// A shell is taken from `$SHELL`, `/bin/sh` is used if it's not set.
+    /// `$TERM` is passed to the shell, `xterm-256color` is used if it's not set.
+    ///
+    /// ```no_run
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_shell();
+    /// ```
+    pub fn spawn_shell() -> Result<Self> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
 
-                    make_controlling_tty(&device)?;
-                    redirect_std_streams(slave_fd)?;
+        let mut command = Command::new(var("SHELL").unwrap_or_else(|| DEFAULT_SHELL.into()));
+        command.arg("-i");
+        command.env("TERM", var("TERM").unwrap_or_else(|| DEFAULT_TERM.into()));
 
-                    set_echo(STDIN_FILENO, false)?;
-                    set_term_size(STDIN_FILENO, DEFAULT_TERM_COLS, DEFAULT_TERM_ROWS)?;
+        Self::spawn(command)
+    }
 
-                    close(exec_err_pipe_read)?;
-                    // close pipe on sucessfull exec
-                    fcntl(exec_err_pipe_write, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+    /// Spawns a child process with a clean environment and a given `TERM` and create a [PtyProcess].
+    ///
+    /// Inherited variables are removed except
+    /// `PATH`, `HOME`, `USER`, `LOGNAME`, `SHELL`, `LANG`, `LC_ALL`, `LC_CTYPE` and `TMPDIR`.
+    /// Variables set on the `command` itself are kept, but `TERM` is always overridden,
+    /// so a child's idea of a terminal is reproducible.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_clean_env(Command::new("bash"), "xterm");
+    /// ```
+    pub fn spawn_clean_env(mut command: Command, term: &str) -> Result<Self> {
+        let explicit = command
+            .get_envs()
+            .map(|(key, value)| (key.to_owned(), value.map(ToOwned::to_owned)))
+            .collect::<Vec<_>>();
+
+        command.env_clear();
+        for name in CLEAN_ENV_VARS {
+            if let Some(value) = std::env::var_os(name) {
+                command.env(name, value);
+            }
+        }
 
-                    // Do not allow child to inherit open file descriptors from parent
-                    //
-                    // on linux could be used getrlimit(RLIMIT_NOFILE, rlim) interface
-                    let max_open_fds = sysconf(SysconfVar::OPEN_MAX)?.unwrap() as i32;
-                    // Why closing FD 1 causes an endless loop
-                    (3..max_open_fds)
-                        .filter(|&fd| fd != slave_fd && fd != exec_err_pipe_write)
-                        .for_each(|fd| {
-                            let _ = close(fd);
-                        });
+        for (key, value) in explicit {
+            match value {
+                Some(value) => command.env(key, value),
+                None => command.env_remove(key),
+            };
+        }
 
-                    let _ = command.exec();
-                    Err(Error::last())
-                }()
-                .unwrap_err();
+        command.env("TERM", term);
 
-                let code = err.as_errno().map_or(-1, |e| e as i32);
+        Self::spawn(command)
+    }
 
-                write(exec_err_pipe_write, &code.to_be_bytes())?;
+    /// Spawns a child process in a given working directory and create a [PtyProcess].
+    ///
+    /// See [PtyProcessBuilder::current_dir].
+    ///
+    /// ```no_run
+    ///   # use std::{path::Path, process::Command};
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_in_dir(Command::new("ls"), Path::new("/tmp")).unwrap();
+    /// ```
+    pub fn spawn_in_dir(command: Command, dir: &Path) -> Result<Self> {
+        PtyProcessBuilder::new()
+            .command(command)
+            .current_dir(dir)
+            .build()
+    }
 
-                process::exit(code);
-            }
-            ForkResult::Parent { child } => {
-                close(exec_err_pipe_write)?;
+    /// Spawns a child process with `stderr` connected to a separate pipe and create a [PtyProcess].
+    ///
+    /// It's useful when errors must be told apart from a regular output (e.g by a test runner).
+    /// `stderr` is read by [PtyProcess::stderr].
+    /// See [PtyProcessBuilder::split_stderr] for caveats.
+    ///
+    /// ```no_run
+    ///   # use std::{io::Read, process::Command};
+    ///   # use ptyprocess::PtyProcess;
+    ///     let mut proc = PtyProcess::spawn_split_stderr(Command::new("ls")).unwrap();
+    ///     let mut errors = String::new();
+    ///     proc.stderr().unwrap().read_to_string(&mut errors).unwrap();
+    /// ```
+    pub fn spawn_split_stderr(command: Command) -> Result<Self> {
+        PtyProcessBuilder::new()
+            .command(command)
+            .split_stderr(true)
+            .build()
+    }
 
-                let mut pipe_buf = [0u8; 4];
-                unistd::read(exec_err_pipe_read, &mut pipe_buf)?;
-                let code = i32::from_be_bytes(pipe_buf);
-                if code != 0 {
-                    return Err(Error::from_errno(errno::from_i32(code)));
-                }
+    /// Spawns a child process and create a [PtyProcess] without making the PTY its controlling terminal.
+    ///
+    /// The child still runs in its own session and its standard streams are connected to the PTY,
+    /// so it gets a terminal line discipline but no job control.
+    /// E.g typing an interrupt character doesn't send `SIGINT` to it,
+    /// and the child can't open `/dev/tty`.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_no_ctty(Command::new("cat"));
+    /// ```
+    pub fn spawn_no_ctty(command: Command) -> Result<Self> {
+        PtyProcessBuilder::new()
+            .command(command)
+            .controlling_tty(false)
+            .build()
+    }
 
-                // Some systems may work in this way? (not sure)
-                // that we need to set a terminal size in a parent.
-                set_term_size(master.as_raw_fd(), DEFAULT_TERM_COLS, DEFAULT_TERM_ROWS)?;
+    /// Spawns a child process and create a [PtyProcess] with terminal settings copied from a given terminal.
+    ///
+    /// The settings (flags, special characters and speed) are read from `from_fd`
+    /// and applied to the PTY before a command is executed, as `script(1)` does,
+    /// so programs behave the same way they do in the terminal they were launched from.
+    /// Echo is turned off anyway, as [PtyProcess::spawn] does.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_inherit_termios(Command::new("bash"), 0);
+    /// ```
+    pub fn spawn_inherit_termios(command: Command, from_fd: RawFd) -> Result<Self> {
+        let flags = termios::tcgetattr(from_fd).map_err(PtyError::spawn)?;
+        PtyProcessBuilder::new()
+            .command(command)
+            .termios(flags)
+            .build()
+    }
 
-                let file = master.get_file_handle()?;
-                let stream = Stream::new(file);
+    /// Spawns a child process and create a [PtyProcess] reporting steps of spawning to an observer.
+    ///
+    /// It may be used to log or trace process creation.
+    /// See [SpawnEvent] for events which are reported.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let proc = PtyProcess::spawn_with_observer(Command::new("bash"), &mut |event| {
+    ///         eprintln!("{:?}", event);
+    ///     });
+    /// ```
+    pub fn spawn_with_observer(
+        command: Command,
+        observer: &mut dyn FnMut(SpawnEvent),
+    ) -> Result<Self> {
+        PtyProcessBuilder::new()
+            .spawn(Program::Command(command), false, Some(observer))
+            .map(|(process, _)| process)
+    }
 
-                Ok(Self {
-                    master,
-                    stream,
-                    child_pid: child,
-                    eof_char,
-                    intr_char,
-                    terminate_approach_delay: Duration::from_millis(100),
-                })
-            }
-        }
+    /// Spawns a child process and create a [PtyProcess] keeping a PTY slave opened in a parent.
+    ///
+    /// The slave is opened before the child is spawned and returned alongside the process.
+    /// While it's alive the PTY is not torn down even if the child closes all its copies of the slave
+    /// (e.g. a program which reopens `/dev/tty`), which makes EOF timing stable.
+    ///
+    /// ```no_run
+    ///   # use std::process::Command;
+    ///   # use ptyprocess::PtyProcess;
+    ///     let (proc, slave) = PtyProcess::spawn_keep_slave(Command::new("bash")).unwrap();
+    /// ```
+    pub fn spawn_keep_slave(command: Command) -> Result<(Self, File)> {
+        let (process, slave) =
+            PtyProcessBuilder::new().spawn(Program::Command(command), true, None)?;
+        // it's always set when requested.
+        let slave = slave.expect("a slave must be opened");
+
+        Ok((process, slave))
+    }
+
+    /// Spawns a child process and runs a given closure in it right before a command is executed.
+    ///
+    /// The closure is run after the PTY is set up,
+    /// so it may be used to set resource limits, drop privileges, etc.
+    /// If it returns an error the command is not executed and the error is returned from this function.
+    ///
+    /// Hooks set by [std::os::unix::process::CommandExt::pre_exec] are run as well,
+    /// it's the same mechanism.
+    ///
+    /// # Safety
+    ///
+    /// The closure is run in a forked process,
+    /// so only async-signal-safe operations are allowed in it.
+    /// E.g it must not allocate memory, acquire locks or use a standard output.
+    /// See [std::os::unix::process::CommandExt::pre_exec] for more information.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::process::Command;
+    ///
+    /// let proc = unsafe {
+    ///     PtyProcess::spawn_with_pre_exec(Command::new("bash"), || {
+    ///         // lower a priority of the child
+    ///         match nix::libc::nice(10) {
+    ///             -1 => Err(std::io::Error::last_os_error()),
+    ///             _ => Ok(()),
+    ///         }
+    ///     })
+    /// };
+    /// ```
+    pub unsafe fn spawn_with_pre_exec<F>(mut command: Command, f: F) -> Result<Self>
+    where
+        F: FnMut() -> io::Result<()> + Send + Sync + 'static,
+    {
+        command.pre_exec(f);
+        Self::spawn(command)
+    }
+
+    /// Spawns a child process which runs a given closure instead of executing a command.
+    ///
+    /// The PTY is set up the same way as for a command,
+    /// and the child exits with a code returned by the closure.
+    /// If the closure panics the child exits with a code 101.
+    ///
+    /// The child exits without running `atexit` handlers and flushing standard streams,
+    /// so an output written to a buffered stream must be flushed by the closure.
+    ///
+    /// It may be used to run a Rust code under a PTY (e.g a test fixture)
+    /// without a separate helper binary.
+    ///
+    /// # Safety
+    ///
+    /// The closure is run in a forked process,
+    /// so if a parent is multi-threaded only async-signal-safe operations are allowed in it.
+    /// E.g a lock held by another thread at the time of a fork (including the one of a standard output)
+    /// is never released in the child.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::io::Write;
+    ///
+    /// let proc = unsafe {
+    ///     PtyProcess::spawn_fn(|| {
+    ///         std::io::stdout().write_all(b"Hello World").unwrap();
+    ///         std::io::stdout().flush().unwrap();
+    ///         0
+    ///     })
+    /// };
+    /// ```
+    pub unsafe fn spawn_fn<F>(f: F) -> Result<Self>
+    where
+        F: FnOnce() -> i32,
+    {
+        PtyProcessBuilder::new()
+            .spawn(Program::Fn(Box::new(f)), false, None)
+            .map(|(process, _)| process)
     }
 
     /// Returns a pid of a child process
@@ -147,8 +428,30 @@ impl PtyProcess {
         self.child_pid
     }
 
+    /// Returns a process group id of a child process.
+    ///
+    /// A child is spawned in its own session, so initially it's a leader of its group
+    /// and the id equals to [PtyProcess::pid].
+    pub fn group_id(&self) -> Result<Pid> {
+        unistd::getpgid(Some(self.child_pid)).map_err(PtyError::from)
+    }
+
+    /// Returns a session id of a child process.
+    ///
+    /// A child is spawned in its own session, so the id equals to [PtyProcess::pid].
+    pub fn session_id(&self) -> Result<Pid> {
+        unistd::getsid(Some(self.child_pid)).map_err(PtyError::from)
+    }
+
     /// Returns a file representation of a PTY, which can be used to communicate with it.
     ///
+    /// # Ownership
+    ///
+    /// The returned file owns its own DUPed descriptor of a PTY master,
+    /// so it stays valid after [PtyProcess] is dropped and the PTY is kept open until it's closed.
+    /// But dropping [PtyProcess] terminates a child (unless it's detached),
+    /// so reads from such a handle return EOF or an `EIO` error once buffered output is consumed.
+    ///
     /// # Safety
     ///
     /// Be carefull changing a descriptors inner state (e.g `fcntl`)
@@ -171,169 +474,1073 @@ impl PtyProcess {
     /// let mut reader = BufReader::new(&pty);
     /// ```
     pub fn get_pty_handle(&self) -> Result<File> {
-        self.master.get_file_handle()
+        self.master.get_file_handle().map_err(PtyError::from)
     }
 
-    /// Get window size of a terminal.
+    /// Checks whether a read won't block, waiting up to a given timeout.
     ///
-    /// Default size is 80x24.
-    pub fn get_window_size(&self) -> Result<(u16, u16)> {
-        get_term_size(self.master.as_raw_fd())
-    }
-
-    /// Sets a terminal size.
-    pub fn set_window_size(&mut self, cols: u16, rows: u16) -> Result<()> {
-        set_term_size(self.master.as_raw_fd(), cols, rows)
-    }
+    /// It returns true if there's buffered data or data can be read from a PTY
+    /// (including `EOF` once a child is gone).
+    /// If the timeout is `None` it waits until data is available.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// let ready = process.has_data_ready(Some(Duration::from_millis(100))).unwrap();
+    /// ```
+    pub fn has_data_ready(&self, timeout: Option<Duration>) -> Result<bool> {
+        if self.stream.has_buffered_data() {
+            return Ok(true);
+        }
 
-    /// Waits until a echo settings is setup.
-    pub fn wait_echo(&self, on: bool, timeout: Option<Duration>) -> Result<bool> {
         let now = time::Instant::now();
-        while timeout.is_none() || now.elapsed() < timeout.unwrap() {
-            if on == self.get_echo()? {
-                return Ok(true);
+        loop {
+            let timeout_ms = match timeout {
+                Some(timeout) => {
+                    let left = timeout.saturating_sub(now.elapsed());
+                    left.as_millis().min(i32::MAX as u128) as i32
+                }
+                None => -1,
+            };
+
+            let mut fds = [PollFd::new(self.master.as_raw_fd(), PollFlags::POLLIN)];
+            match poll(&mut fds, timeout_ms) {
+                Ok(n) => return Ok(n > 0),
+                Err(Error::Sys(Errno::EINTR)) => continue,
+                Err(err) => return Err(PtyError::from(err)),
             }
-
-            thread::sleep(Duration::from_millis(100));
         }
-
-        Ok(false)
     }
 
-    /// The function returns true if an echo setting is setup.
-    pub fn get_echo(&self) -> Result<bool> {
-        termios::tcgetattr(self.master.as_raw_fd())
-            .map(|flags| flags.local_flags.contains(termios::LocalFlags::ECHO))
+    /// Registers a session of a given user on the PTY in utmp,
+    /// so it's listed by tools like `who` and `w`.
+    ///
+    /// The record is marked as finished once [PtyProcess] is dropped (unless it's detached)
+    /// or [PtyProcess::unregister_utmp] is called.
+    /// A repeated call replaces the record.
+    ///
+    /// Writing utmp usually requires privileges (e.g. being a member of a `utmp` group).
+    ///
+    /// It's available only on Linux with a `utmp` feature.
+    #[cfg(all(feature = "utmp", target_os = "linux"))]
+    pub fn register_utmp(&self, user: &str) -> Result<()> {
+        let entry = UtmpEntry::register(self.child_pid, &self.slave_name()?, user)?;
+        *self.utmp_entry.lock().unwrap() = Some(entry);
+        Ok(())
     }
 
-    /// Sets a echo setting for a terminal
-    pub fn set_echo(&mut self, on: bool) -> Result<()> {
-        set_echo(self.master.as_raw_fd(), on)
+    /// Marks a session registered by [PtyProcess::register_utmp] as finished.
+    ///
+    /// It does nothing if a session is not registered.
+    #[cfg(all(feature = "utmp", target_os = "linux"))]
+    pub fn unregister_utmp(&self) -> Result<()> {
+        match self.utmp_entry.lock().unwrap().take() {
+            Some(entry) => entry.unregister().map_err(PtyError::from),
+            None => Ok(()),
+        }
     }
 
-    /// Returns true if a underline `fd` connected with a TTY.
-    pub fn isatty(&self) -> Result<bool> {
-        isatty(self.master.as_raw_fd())
+    /// Returns an amount of bytes which can be read without blocking.
+    ///
+    /// It's a sum of bytes in an internal buffer and bytes waiting in a PTY (`FIONREAD`).
+    /// More data may arrive right after the call.
+    pub fn bytes_available(&self) -> Result<usize> {
+        let pending = get_pending_bytes(self.master.as_raw_fd())?;
+        Ok(self.stream.buffered_len() + pending)
     }
 
-    /// Set the pty process's terminate approach delay.
-    pub fn set_terminate_approach_delay(&mut self, terminate_approach_delay: Duration) {
-        self.terminate_approach_delay = terminate_approach_delay;
+    /// Returns a name of a PTY slave device (e.g. `/dev/pts/3`).
+    pub fn slave_name(&self) -> Result<String> {
+        self.master.get_slave_name().map_err(PtyError::from)
     }
 
-    /// Status returns a status a of child process.
-    pub fn status(&self) -> Result<WaitStatus> {
-        waitpid(self.child_pid, Some(wait::WaitPidFlag::WNOHANG))
+    /// Opens a new descriptor of a PTY slave device.
+    ///
+    /// The slave is opened with `O_NOCTTY`,
+    /// so it doesn't become a controlling terminal of a calling process.
+    /// The returned file may be handed to another process.
+    pub fn open_slave(&self) -> Result<File> {
+        self.master.open_slave().map_err(PtyError::from)
     }
 
-    /// Kill sends a signal to a child process.
+    /// Blocks until all output written to a PTY is transmitted (`tcdrain`).
     ///
-    /// The operation is non-blocking.
-    pub fn kill(&mut self, signal: signal::Signal) -> Result<()> {
-        signal::kill(self.child_pid, signal)
+    /// Unlike [std::io::Write::flush], which only pushes an internal buffer to the fd,
+    /// it waits for the terminal driver to transmit written bytes.
+    pub fn wait_output_drained(&mut self) -> Result<()> {
+        termios::tcdrain(self.master.as_raw_fd()).map_err(PtyError::from)
     }
 
-    /// Signal is an alias to [PtyProcess::kill].
+    /// Discards input which was sent to a child but not read by it yet.
     ///
-    /// [PtyProcess::kill]: struct.PtyProcess.html#method.kill
-    pub fn signal(&mut self, signal: signal::Signal) -> Result<()> {
-        self.kill(signal)
+    /// It may be used to drop a half-typed input after aborting a command.
+    pub fn discard_input(&mut self) -> Result<()> {
+        // the input queue belongs to the slave side
+        let slave = self.master.open_slave()?;
+        termios::tcflush(slave.as_raw_fd(), termios::FlushArg::TCIFLUSH).map_err(PtyError::from)
     }
 
-    /// Wait blocks until a child process exits.
+    /// Discards output which was produced by a child but not read yet.
     ///
-    /// It returns a error if the child was DEAD or not exist
-    /// at the time of a call.
+    /// Notice that output which is already read into an internal buffer is not discarded.
     ///
-    /// If you need to verify that a process is dead in non-blocking way you can use
-    /// [is_alive] method.
+    /// Unlike what a name may suggest it flushes an input queue (`TCIFLUSH`) of a PTY master,
+    /// as a child's output is an input from the master's point of view.
+    pub fn discard_output(&mut self) -> Result<()> {
+        // The master's input queue holds what a child wrote to the slave.
+        // The master's TCOFLUSH on the other hand doesn't reach data a child hasn't read yet on Linux,
+        // that's why discard_input goes through the slave.
+        termios::tcflush(self.master.as_raw_fd(), termios::FlushArg::TCIFLUSH)
+            .map_err(PtyError::from)
+    }
+
+    /// Discards both pending input and output.
     ///
-    /// [is_alive]: struct.PtyProcess.html#method.is_alive
-    pub fn wait(&self) -> Result<WaitStatus> {
-        waitpid(self.child_pid, None)
+    /// See [PtyProcess::discard_input] and [PtyProcess::discard_output].
+    ///
+    /// It's not a single `TCIOFLUSH` on a PTY master, as it leaves a child's pending input on Linux.
+    /// The input is flushed through the slave and the output through the master,
+    /// so the operation isn't atomic and a child may produce output in between.
+    /// If the slave can't be opened nothing is discarded.
+    pub fn discard_io(&mut self) -> Result<()> {
+        let slave = self.master.open_slave()?;
+        termios::tcflush(slave.as_raw_fd(), termios::FlushArg::TCIFLUSH)?;
+        termios::tcflush(self.master.as_raw_fd(), termios::FlushArg::TCIFLUSH)
+            .map_err(PtyError::from)
     }
 
-    /// Checks if a process is still exists.
+    /// Sends a `BREAK` condition to a terminal.
     ///
-    /// It's a non blocking operation.
+    /// If a duration is given the condition is held for that long (`TIOCSBRK`/`TIOCCBRK`),
+    /// otherwise a standard short break is sent (`tcsendbreak`).
     ///
-    /// Keep in mind that after calling this method process might be marked as DEAD by kernel,
-    /// because a check of its status.
-    /// Therefore second call to [Self::status] or [Self::is_alive] might return a different status.
-    pub fn is_alive(&self) -> Result<bool> {
-        let status = self.status();
-        match status {
-            Ok(status) if status == WaitStatus::StillAlive => Ok(true),
-            Ok(_) | Err(Error::Sys(Errno::ECHILD)) | Err(Error::Sys(Errno::ESRCH)) => Ok(false),
-            Err(err) => Err(err),
+    /// The call blocks until the break is over.
+    pub fn send_break(&mut self, duration: Option<Duration>) -> Result<()> {
+        let fd = self.master.as_raw_fd();
+        match duration {
+            Some(duration) => {
+                set_break(fd, true)?;
+                thread::sleep(duration);
+                set_break(fd, false).map_err(PtyError::from)
+            }
+            None => termios::tcsendbreak(fd, 0).map_err(PtyError::from),
         }
     }
 
-    /// Try to force a child to terminate.
+    /// Pushes bytes into a child's input queue as if they were typed on a terminal (`TIOCSTI`).
     ///
-    /// This returns true if the child was terminated. and returns false if the
-    /// child could not be terminated.
+    /// Unlike writing to a PTY, bytes are inserted directly into the input queue of the slave.
     ///
-    /// It makes 4 tries getting more thorough.
+    /// On hardened systems the operation may be restricted,
+    /// e.g. Linux requires `CAP_SYS_ADMIN` for a terminal which is not a controlling one,
+    /// and since 6.2 it may be disabled altogether by `dev.tty.legacy_tiocsti` sysctl.
+    /// In such case an error with [io::ErrorKind::PermissionDenied] kind is returned.
+    pub fn push_to_input(&mut self, bytes: &[u8]) -> Result<()> {
+        let slave = self.master.open_slave()?;
+        push_to_input(slave.as_raw_fd(), bytes).map_err(|err| match err {
+            Error::Sys(Errno::EPERM) | Error::Sys(Errno::EIO) => PtyError::Io(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                "TIOCSTI is not permitted, it requires CAP_SYS_ADMIN or may be disabled by the system",
+            )),
+            err => PtyError::from(err),
+        })
+    }
+
+    /// Wraps a process into [BufferedPtyProcess], which implements buffered reading traits.
     ///
-    /// 1. SIGHUP
-    /// 2. SIGCONT
-    /// 3. SIGINT
-    /// 4. SIGTERM
+    /// It reuses a buffer of the process' stream, so no data is lost or read twice,
+    /// and the process can be taken back by [BufferedPtyProcess::into_inner].
+    pub fn buffered(self) -> BufferedPtyProcess {
+        BufferedPtyProcess::new(self)
+    }
+
+    /// Splits a PTY into independent reading and writing halves.
     ///
-    /// If "force" is `true` then moves onto SIGKILL.
-    pub fn exit(&mut self, force: bool) -> Result<bool> {
-        if !self.is_alive()? {
-            return Ok(true);
-        }
+    /// Each half owns its own DUPed descriptor of a PTY master,
+    /// so dropping one of them doesn't affect the other nor the [PtyProcess].
+    ///
+    /// An example for the `sync` version.
+    ///
+    /// ```no_run,ignore
+    /// # use std::io::{BufRead, Write};
+    /// # use std::process::Command;
+    /// # use ptyprocess::PtyProcess;
+    /// let process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// let (mut reader, mut writer) = process.split().unwrap();
+    ///
+    /// std::thread::spawn(move || writer.write_all(b"Hello World\n").unwrap());
+    ///
+    /// let mut line = String::new();
+    /// reader.read_line(&mut line).unwrap();
+    /// ```
+    pub fn split(&self) -> Result<(PtyReader, PtyWriter)> {
+        let reader = PtyReader::new(self.get_pty_handle()?);
+        let writer = PtyWriter::new(self.get_pty_handle()?);
+        Ok((reader, writer))
+    }
 
-        for &signal in &[
-            signal::SIGHUP,
-            signal::SIGCONT,
-            signal::SIGINT,
-            signal::SIGTERM,
-        ] {
-            if self.try_to_terminate(signal)? {
-                return Ok(true);
-            }
+    /// Takes a writing half of the PTY, which is child's input.
+    ///
+    /// It's similar to taking [std::process::Child::stdin].
+    /// The writer can be taken only once, `Ok(None)` is returned afterwards.
+    /// It owns its own descriptor of the PTY, so it stays usable on its own;
+    /// the [PtyProcess] is still responsible for a life cycle of the child.
+    pub fn take_stdin(&mut self) -> Result<Option<PtyWriter>> {
+        if self.stdin_taken {
+            return Ok(None);
         }
 
-        if !force {
-            return Ok(false);
-        }
+        let writer = PtyWriter::new(self.get_pty_handle()?);
+        self.stdin_taken = true;
 
-        self.try_to_terminate(SIGKILL)
+        Ok(Some(writer))
     }
 
-    fn try_to_terminate(&mut self, signal: signal::Signal) -> Result<bool> {
-        self.kill(signal)?;
-        thread::sleep(self.terminate_approach_delay);
+    /// Takes a reading half of the PTY, which is child's output.
+    ///
+    /// It's similar to taking [std::process::Child::stdout].
+    /// The reader can be taken only once, `Ok(None)` is returned afterwards.
+    ///
+    /// Data already buffered by the [PtyProcess] is moved to the reader, so nothing is lost.
+    /// The [PtyProcess] must not be read from afterwards,
+    /// otherwise data is split between it and the reader.
+    pub fn take_stdout(&mut self) -> Result<Option<PtyReader>> {
+        if self.stdout_taken {
+            return Ok(None);
+        }
 
-        self.is_alive().map(|is_alive| !is_alive)
+        let stream = Stream::with_capacity(self.stream_capacity, self.get_pty_handle()?);
+        let stream = std::mem::replace(&mut self.stream, stream);
+        self.stdout_taken = true;
+
+        Ok(Some(PtyReader::from_stream(stream)))
     }
-}
 
-#[cfg(feature = "sync")]
-impl PtyProcess {
-    /// Send text to child's `STDIN`.
+    /// Returns a reading end of a pipe connected to child's `stderr`.
     ///
-    /// To write bytes you can use a [std::io::Write] operations instead.
-    pub fn send<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
-        self.stream.write_all(s.as_ref().as_bytes())
+    /// It's `None` unless the child was spawned with a separate `stderr`
+    /// (see [PtyProcessBuilder::split_stderr]) or it was taken by [PtyProcess::take_stderr].
+    pub fn stderr(&mut self) -> Option<&mut File> {
+        self.stderr.as_mut()
     }
 
-    /// Send a line to child's `STDIN`.
-    pub fn send_line<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
-        #[cfg(windows)]
-        const LINE_ENDING: &[u8] = b"\r\n";
-        #[cfg(not(windows))]
-        const LINE_ENDING: &[u8] = b"\n";
-
-        let bufs = &mut [
-            std::io::IoSlice::new(s.as_ref().as_bytes()),
-            std::io::IoSlice::new(LINE_ENDING),
-            std::io::IoSlice::new(&[]), // we need to add a empty one as it may be not written.
-        ];
+    /// Takes a reading end of a pipe connected to child's `stderr`.
+    ///
+    /// See [PtyProcess::stderr].
+    pub fn take_stderr(&mut self) -> Option<File> {
+        self.stderr.take()
+    }
+
+    /// Get window size of a terminal.
+    ///
+    /// Default size is 80x24.
+    pub fn get_window_size(&self) -> Result<(u16, u16)> {
+        get_term_size(self.master.as_raw_fd()).map_err(PtyError::winsize)
+    }
+
+    /// Sets a terminal size.
+    ///
+    /// It only changes the size,
+    /// use [PtyProcess::resize] to be sure the child is notified about it.
+    ///
+    /// The size is not validated,
+    /// use [PtyProcess::set_window_size_checked] to reject zero dimensions.
+    pub fn set_window_size(&mut self, cols: u16, rows: u16) -> Result<()> {
+        set_term_size(self.master.as_raw_fd(), cols, rows).map_err(PtyError::winsize)
+    }
+
+    /// Sets a terminal size rejecting zero dimensions.
+    ///
+    /// A zero size is accepted by a kernel but many programs can't handle it
+    /// (e.g some curses programs divide by a number of rows).
+    /// It returns [PtyError::Winsize] error with `EINVAL` if `cols` or `rows` is 0.
+    pub fn set_window_size_checked(&mut self, cols: u16, rows: u16) -> Result<()> {
+        if cols == 0 || rows == 0 {
+            return Err(PtyError::winsize(Error::Sys(Errno::EINVAL)));
+        }
+
+        self.set_window_size(cols, rows)
+    }
+
+    /// Get window size of a terminal including its pixel dimensions.
+    ///
+    /// It returns `(cols, rows, xpixel, ypixel)`.
+    /// Pixel dimensions are 0 unless they were set.
+    pub fn get_window_size_pixels(&self) -> Result<(u16, u16, u16, u16)> {
+        get_winsize(self.master.as_raw_fd())
+            .map(|size| (size.ws_col, size.ws_row, size.ws_xpixel, size.ws_ypixel))
+            .map_err(PtyError::winsize)
+    }
+
+    /// Sets a terminal size including its pixel dimensions.
+    ///
+    /// Pixel dimensions are used by programs which render graphics (e.g sixel).
+    pub fn set_window_size_pixels(
+        &mut self,
+        cols: u16,
+        rows: u16,
+        xpixel: u16,
+        ypixel: u16,
+    ) -> Result<()> {
+        let size = winsize {
+            ws_col: cols,
+            ws_row: rows,
+            ws_xpixel: xpixel,
+            ws_ypixel: ypixel,
+        };
+
+        set_winsize(self.master.as_raw_fd(), &size).map_err(PtyError::winsize)
+    }
+
+    /// Sets a terminal size to the size of a given terminal (e.g `STDIN_FILENO`).
+    ///
+    /// Pixel dimensions are copied as well.
+    /// It only changes the size,
+    /// use [PtyProcess::resize] to be sure the child is notified about it.
+    pub fn set_window_size_from_terminal(&mut self, fd: RawFd) -> Result<()> {
+        let size = get_winsize(fd).map_err(PtyError::winsize)?;
+        set_winsize(self.master.as_raw_fd(), &size).map_err(PtyError::winsize)
+    }
+
+    /// Sets a terminal size from `$COLUMNS` and `$LINES` environment variables.
+    ///
+    /// It's a fallback for [PtyProcess::set_window_size_from_terminal]
+    /// when there's no terminal to get the size from.
+    ///
+    /// It returns [PtyError::Winsize] error if the variables are not set or not valid.
+    pub fn set_window_size_from_env(&mut self) -> Result<()> {
+        let (cols, rows) = get_env_winsize(|var| std::env::var(var))?;
+        self.set_window_size(cols, rows)
+    }
+
+    /// Sets a terminal size and sends `SIGWINCH` to a child process.
+    ///
+    /// Some platforms don't deliver `SIGWINCH` on a size change,
+    /// while many full-screen programs redraw only when they get it.
+    pub fn resize(&mut self, cols: u16, rows: u16) -> Result<()> {
+        self.set_window_size(cols, rows)?;
+        self.kill(signal::SIGWINCH)
+    }
+
+    /// Copies a size of a given terminal (e.g `STDIN_FILENO`) to the PTY and sends `SIGWINCH` to a child process.
+    ///
+    /// It's supposed to be called once a program gets `SIGWINCH` itself,
+    /// so the child follows size changes of a terminal the program is run in.
+    /// As a signal handler must not do much, it's usually called from a main loop
+    /// after the handler sets a flag.
+    ///
+    /// ```no_run
+    /// use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, sync::atomic::{AtomicBool, Ordering}};
+    ///
+    /// static RESIZED: AtomicBool = AtomicBool::new(false);
+    ///
+    /// extern "C" fn on_winch(_: i32) {
+    ///     RESIZED.store(true, Ordering::SeqCst);
+    /// }
+    ///
+    /// let action = SigAction::new(SigHandler::Handler(on_winch), SaFlags::SA_RESTART, SigSet::empty());
+    /// unsafe { sigaction(Signal::SIGWINCH, &action) }.unwrap();
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("vim")).unwrap();
+    /// loop {
+    ///     if RESIZED.swap(false, Ordering::SeqCst) {
+    ///         process.forward_winch(0).unwrap();
+    ///     }
+    ///
+    ///     // relay IO
+    /// }
+    /// ```
+    pub fn forward_winch(&mut self, from_fd: RawFd) -> Result<()> {
+        self.set_window_size_from_terminal(from_fd)?;
+        self.kill(signal::SIGWINCH)
+    }
+
+    /// Waits until a echo settings is setup.
+    pub fn wait_echo(&self, on: bool, timeout: Option<Duration>) -> Result<bool> {
+        let now = time::Instant::now();
+        while timeout.is_none() || now.elapsed() < timeout.unwrap() {
+            if on == self.get_echo()? {
+                return Ok(true);
+            }
+
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        Ok(false)
+    }
+
+    /// The function returns true if an echo setting is setup.
+    pub fn get_echo(&self) -> Result<bool> {
+        self.get_local_flags()
+            .map(|flags| flags.contains(termios::LocalFlags::ECHO))
+    }
+
+    /// Sets a echo setting for a terminal
+    pub fn set_echo(&mut self, on: bool) -> Result<()> {
+        self.set_local_flags(termios::LocalFlags::ECHO, on)
+    }
+
+    /// Returns local flags of a terminal (e.g `ECHO`, `ICANON`).
+    pub fn get_local_flags(&self) -> Result<termios::LocalFlags> {
+        self.get_termios().map(|flags| flags.local_flags)
+    }
+
+    /// Turns given local flags of a terminal on or off at once, leaving other flags as they are.
+    ///
+    /// Related flags are usually changed together (e.g `ECHO | ECHOE | ECHOK | ECHONL`),
+    /// which is done by a single `tcsetattr` call rather than by one call per flag.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, termios::LocalFlags};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.set_local_flags(LocalFlags::ICANON | LocalFlags::ECHO, false).unwrap();
+    /// ```
+    pub fn set_local_flags(&mut self, flags: termios::LocalFlags, on: bool) -> Result<()> {
+        set_local_flags(self.master.as_raw_fd(), flags, on).map_err(PtyError::from)
+    }
+
+    /// Returns terminal attributes of a PTY.
+    pub fn get_termios(&self) -> Result<termios::Termios> {
+        termios::tcgetattr(self.master.as_raw_fd()).map_err(PtyError::from)
+    }
+
+    /// Sets terminal attributes of a PTY.
+    ///
+    /// The change occurs immediately.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, termios::LocalFlags};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// let mut flags = process.get_termios().unwrap();
+    /// flags.local_flags.remove(LocalFlags::ICANON | LocalFlags::ISIG);
+    /// process.set_termios(&flags).unwrap();
+    /// ```
+    pub fn set_termios(&mut self, flags: &termios::Termios) -> Result<()> {
+        termios::tcsetattr(self.master.as_raw_fd(), termios::SetArg::TCSANOW, flags)
+            .map_err(PtyError::from)
+    }
+
+    /// Returns terminal attributes of a PTY as they were right after a spawn.
+    ///
+    /// They include settings made by a builder (e.g [PtyProcessBuilder::echo]).
+    pub fn initial_termios(&self) -> termios::Termios {
+        self.initial_termios.lock().unwrap().clone()
+    }
+
+    /// Checks whether terminal attributes of a PTY differ from the ones it had right after a spawn.
+    ///
+    /// Programs like `vim` change the attributes and may leave them changed
+    /// if they were killed, so a supervisor may decide whether to reset a terminal.
+    ///
+    /// ```no_run
+    /// # use ptyprocess::PtyProcess;
+    /// # use std::process::Command;
+    /// let mut process = PtyProcess::spawn(Command::new("vim")).unwrap();
+    /// // ...
+    /// if process.termios_changed_since_spawn().unwrap() {
+    ///     let flags = process.initial_termios();
+    ///     process.set_termios(&flags).unwrap();
+    /// }
+    /// ```
+    ///
+    /// The snapshot is taken once the child is executed,
+    /// so a change made by the child at its very start might be missed.
+    pub fn termios_changed_since_spawn(&self) -> Result<bool> {
+        self.get_termios()
+            .map(|flags| flags != *self.initial_termios.lock().unwrap())
+    }
+
+    /// Puts a terminal into a raw mode.
+    ///
+    /// Input is available character by character,
+    /// echoing is disabled and special characters are not processed.
+    pub fn set_raw_mode(&mut self) -> Result<()> {
+        set_raw(self.master.as_raw_fd()).map_err(PtyError::from)
+    }
+
+    /// Puts a terminal into a canonical mode.
+    ///
+    /// It turns on `ICANON`, `ECHO`, `ISIG` and `IEXTEN` local flags
+    /// together with input `CR` to `NL` translation and output processing,
+    /// so it reverts the [PtyProcess::set_raw_mode] call.
+    pub fn set_canonical_mode(&mut self) -> Result<()> {
+        set_canonical(self.master.as_raw_fd()).map_err(PtyError::from)
+    }
+
+    /// Returns a character which is sent by [PtyProcess::send_eof].
+    ///
+    /// By default it's a `VEOF` character of the PTY.
+    pub fn get_eof_char(&self) -> u8 {
+        self.eof_char
+    }
+
+    /// Sets a character which is sent by [PtyProcess::send_eof].
+    pub fn set_eof_char(&mut self, c: u8) {
+        self.eof_char = c;
+    }
+
+    /// Returns a character which is sent by [PtyProcess::send_intr].
+    ///
+    /// By default it's a `VINTR` character of the PTY.
+    pub fn get_intr_char(&self) -> u8 {
+        self.intr_char
+    }
+
+    /// Sets a character which is sent by [PtyProcess::send_intr].
+    pub fn set_intr_char(&mut self, c: u8) {
+        self.intr_char = c;
+    }
+
+    /// Returns a special character of a terminal (e.g `VERASE`).
+    pub fn get_term_char(&self, index: SpecialCharacterIndices) -> Result<u8> {
+        get_term_char(self.master.as_raw_fd(), index).map_err(PtyError::from)
+    }
+
+    /// Sets a special character of a terminal (e.g `VERASE`).
+    ///
+    /// Setting `VEOF` or `VINTR` also changes characters sent by
+    /// [PtyProcess::send_eof] and [PtyProcess::send_intr] accordingly.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{termios::SpecialCharacterIndices, PtyProcess};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// // use backspace instead of delete
+    /// process.set_term_char(SpecialCharacterIndices::VERASE, 0x8).unwrap();
+    /// ```
+    pub fn set_term_char(&mut self, index: SpecialCharacterIndices, c: u8) -> Result<()> {
+        let mut flags = self.get_termios()?;
+        flags.control_chars[index as usize] = c;
+        self.set_termios(&flags)?;
+
+        match index {
+            SpecialCharacterIndices::VEOF => self.eof_char = c,
+            SpecialCharacterIndices::VINTR => self.intr_char = c,
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Returns true if a underline `fd` connected with a TTY.
+    pub fn isatty(&self) -> Result<bool> {
+        isatty(self.master.as_raw_fd()).map_err(PtyError::from)
+    }
+
+    /// Set the pty process's terminate approach delay.
+    pub fn set_terminate_approach_delay(&mut self, terminate_approach_delay: Duration) {
+        self.terminate_approach_delay = terminate_approach_delay;
+    }
+
+    /// Sets how long an idle interaction waits before checking a child status again.
+    ///
+    /// In a sync version the wait is interrupted as soon as a child produces output
+    /// (or `stdin` is ready in case of `interact`),
+    /// so the interval mostly affects a latency of an input given to `interact_io`.
+    ///
+    /// The default is 10 milliseconds.
+    pub fn set_interact_poll_interval(&mut self, interval: Duration) {
+        self.interact_poll_interval = interval;
+    }
+
+    /// Status returns a status a of child process.
+    ///
+    /// Once the child is reaped its status is cached,
+    /// so repeated calls return the same status.
+    pub fn status(&self) -> Result<WaitStatus> {
+        self.reap(Some(wait::WaitPidFlag::WNOHANG))
+    }
+
+    /// Returns a status of a child process including stops (`WUNTRACED`).
+    ///
+    /// Unlike [PtyProcess::status] it returns [WaitStatus::Stopped] if a child was stopped
+    /// (e.g by `SIGTSTP` or `SIGSTOP`).
+    /// Each stop is reported only once, after that [PtyProcess::is_stopped] doesn't see it.
+    pub fn status_untraced(&self) -> Result<WaitStatus> {
+        self.reap(Some(
+            wait::WaitPidFlag::WNOHANG | wait::WaitPidFlag::WUNTRACED,
+        ))
+    }
+
+    /// Checks whether a child process is stopped (e.g by `SIGTSTP` or `SIGSTOP`).
+    ///
+    /// It doesn't consume a stop (`WNOWAIT`) so it can be checked repeatedly,
+    /// until the child is continued (e.g by [PtyProcess::resume])
+    /// or the stop is consumed by [PtyProcess::status_untraced].
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, Signal};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.kill(Signal::SIGSTOP).unwrap();
+    /// // ...
+    /// if process.is_stopped().unwrap() {
+    ///     process.resume().unwrap();
+    /// }
+    /// ```
+    pub fn is_stopped(&self) -> Result<bool> {
+        if self.exit_status.lock().unwrap().is_some() {
+            return Ok(false);
+        }
+
+        match has_stopped(self.child_pid) {
+            Ok(stopped) => Ok(stopped),
+            Err(Error::Sys(Errno::ECHILD)) => Ok(false),
+            Err(err) => Err(PtyError::from(err)),
+        }
+    }
+
+    /// Continues a stopped child process by sending `SIGCONT`.
+    pub fn resume(&mut self) -> Result<()> {
+        self.kill(signal::SIGCONT)
+    }
+
+    /// Checks whether a child process was reaped (e.g by [PtyProcess::wait]).
+    ///
+    /// A reaped child doesn't exist anymore,
+    /// its status is cached and returned by [PtyProcess::status] and [PtyProcess::wait].
+    pub fn is_reaped(&self) -> bool {
+        self.exit_status.lock().unwrap().is_some()
+    }
+
+    /// Kill sends a signal to a child process.
+    ///
+    /// It returns [PtyError::Terminated] once the child is reaped,
+    /// as its pid may be reused by an unrelated process.
+    ///
+    /// The operation is non-blocking.
+    pub fn kill(&mut self, signal: signal::Signal) -> Result<()> {
+        if self.is_reaped() {
+            return Err(PtyError::Terminated);
+        }
+
+        signal::kill(self.child_pid, signal).map_err(PtyError::from)
+    }
+
+    /// Kill group sends a signal to a process group of a child.
+    ///
+    /// A child is a process group leader,
+    /// so the signal also reaches processes it spawned (e.g a pipeline started by a shell).
+    ///
+    /// It returns [PtyError::Terminated] once the child is reaped,
+    /// as its pid may be reused as an id of an unrelated group.
+    ///
+    /// The operation is non-blocking.
+    pub fn kill_group(&mut self, signal: signal::Signal) -> Result<()> {
+        if self.is_reaped() {
+            return Err(PtyError::Terminated);
+        }
+
+        signal::killpg(self.child_pid, signal).map_err(PtyError::from)
+    }
+
+    /// Returns an id of a process group which is currently in the foreground of the PTY.
+    ///
+    /// It may be used to find out which job a shell is running.
+    /// If a child doesn't run jobs in separate groups the foreground group is its own one.
+    ///
+    /// The group is taken by `tcgetpgrp` on the master side,
+    /// as a slave may be queried only by processes it's a controlling terminal of.
+    /// It's supported on Linux; other systems may return an error.
+    /// It returns an error with [io::ErrorKind::NotFound] kind if the PTY has no foreground process group
+    /// (e.g it's not a controlling terminal of a child).
+    pub fn foreground_process_group(&self) -> Result<Pid> {
+        let pgid = tcgetpgrp(self.master.as_raw_fd())?;
+        if pgid.as_raw() <= 0 {
+            return Err(PtyError::Io(io::Error::new(
+                io::ErrorKind::NotFound,
+                "a PTY has no foreground process group",
+            )));
+        }
+
+        Ok(pgid)
+    }
+
+    /// Sends a signal to a foreground process group of the PTY.
+    ///
+    /// It's how a terminal delivers signals generated by control characters (e.g `^C`),
+    /// so the signal reaches a foreground job of a shell rather than the shell itself.
+    ///
+    /// See [PtyProcess::foreground_process_group].
+    pub fn kill_foreground(&mut self, signal: signal::Signal) -> Result<()> {
+        let pgid = self.foreground_process_group()?;
+        signal::killpg(pgid, signal).map_err(PtyError::from)
+    }
+
+    /// Sends `SIGINT` to a foreground process group of the PTY.
+    ///
+    /// See [PtyProcess::kill_foreground].
+    pub fn interrupt_foreground(&mut self) -> Result<()> {
+        self.kill_foreground(signal::SIGINT)
+    }
+
+    /// Signal is an alias to [PtyProcess::kill].
+    ///
+    /// [PtyProcess::kill]: struct.PtyProcess.html#method.kill
+    pub fn signal(&mut self, signal: signal::Signal) -> Result<()> {
+        self.kill(signal)
+    }
+
+    /// Wait blocks until a child process exits.
+    ///
+    /// If the child was already reaped its cached status is returned,
+    /// so it's safe to call it more then once.
+    /// It returns [PtyError::Terminated] error if the child was reaped
+    /// by someone else (e.g by a `waitpid` call on [PtyProcess::pid]).
+    ///
+    /// If you need to verify that a process is dead in non-blocking way you can use
+    /// [is_alive] method.
+    ///
+    /// [is_alive]: struct.PtyProcess.html#method.is_alive
+    pub fn wait(&self) -> Result<WaitStatus> {
+        self.reap(None)
+    }
+
+    /// Wait blocks until a child process exits and returns its status as [PtyExitStatus].
+    ///
+    /// It's the same as [PtyProcess::wait] but the status is easier to inspect.
+    pub fn wait_status(&self) -> Result<PtyExitStatus> {
+        self.wait().map(PtyExitStatus::from)
+    }
+
+    /// Wait blocks until a child process exits and returns its exit code.
+    ///
+    /// If the child was terminated by a signal the code is `128 + signal`,
+    /// as a shell does.
+    ///
+    /// It returns a error if the child changed its state in other way (e.g was stopped).
+    pub fn wait_exit_code(&self) -> Result<i32> {
+        match self.wait()? {
+            WaitStatus::Exited(_, code) => Ok(code),
+            WaitStatus::Signaled(_, signal, _) => Ok(128 + signal as i32),
+            status => Err(PtyError::UnexpectedStatus(status)),
+        }
+    }
+
+    /// Wait blocks until a child process exits or a timeout is reached.
+    ///
+    /// It returns `Ok(None)` if the child is still alive after the timeout.
+    ///
+    /// The status is polled every 100 milliseconds,
+    /// so the call may take a bit longer than the given timeout.
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<Option<WaitStatus>> {
+        let now = time::Instant::now();
+        loop {
+            let status = self.status()?;
+            if status != WaitStatus::StillAlive {
+                return Ok(Some(status));
+            }
+
+            if now.elapsed() >= timeout {
+                return Ok(None);
+            }
+
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    /// Sends a signal to a child process and waits until it exits.
+    ///
+    /// If the child is still alive after the timeout it's killed by `SIGKILL`
+    /// and waited for once more.
+    /// It returns a final status of the child, which is reaped afterwards.
+    /// If the child was already reaped its cached status is returned without sending anything.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, Signal};
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// let status = process
+    ///     .kill_and_wait(Signal::SIGTERM, Duration::from_secs(1))
+    ///     .unwrap();
+    /// ```
+    pub fn kill_and_wait(
+        &mut self,
+        signal: signal::Signal,
+        timeout: Duration,
+    ) -> Result<WaitStatus> {
+        if let Some(status) = *self.exit_status.lock().unwrap() {
+            return Ok(status);
+        }
+
+        self.kill(signal)?;
+        if let Some(status) = self.wait_timeout(timeout)? {
+            return Ok(status);
+        }
+
+        self.kill(SIGKILL)?;
+        self.wait()
+    }
+
+    /// Try wait checks whether a child process has exited without blocking.
+    ///
+    /// It returns `Ok(Some(status))` if the child exited or was terminated by a signal,
+    /// and `Ok(None)` otherwise.
+    ///
+    /// Once the child is reaped its status is cached,
+    /// so repeated calls return the same status.
+    pub fn try_wait(&self) -> Result<Option<WaitStatus>> {
+        match self.status()? {
+            status @ WaitStatus::Exited(..) | status @ WaitStatus::Signaled(..) => Ok(Some(status)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Checks if a process is still exists.
+    ///
+    /// It's a non blocking operation.
+    ///
+    /// Keep in mind that a terminated child is reaped by this method,
+    /// its status is cached and returned by following calls to [Self::status] and [Self::wait].
+    /// Use [Self::is_alive_nowait] to keep a child unreaped.
+    pub fn is_alive(&self) -> Result<bool> {
+        let status = self.status();
+        match status {
+            Ok(WaitStatus::StillAlive) => Ok(true),
+            Ok(_) | Err(PtyError::Terminated) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Checks if a process is still exists without reaping it.
+    ///
+    /// Unlike [Self::is_alive] it doesn't consume an exit status of a terminated child (`WNOWAIT`),
+    /// so it can be polled repeatedly and [Self::wait] still returns the real status afterwards.
+    ///
+    /// It's a non blocking operation.
+    pub fn is_alive_nowait(&self) -> Result<bool> {
+        if self.exit_status.lock().unwrap().is_some() {
+            return Ok(false);
+        }
+
+        match has_exited(self.child_pid) {
+            Ok(exited) => Ok(!exited),
+            Err(Error::Sys(Errno::ECHILD)) => Ok(false),
+            Err(err) => Err(PtyError::from(err)),
+        }
+    }
+
+    /// Try to force a child to terminate.
+    ///
+    /// This returns true if the child was terminated. and returns false if the
+    /// child could not be terminated.
+    ///
+    /// It makes 4 tries getting more thorough.
+    ///
+    /// 1. SIGHUP
+    /// 2. SIGCONT
+    /// 3. SIGINT
+    /// 4. SIGTERM
+    ///
+    /// If "force" is `true` then moves onto SIGKILL.
+    pub fn exit(&mut self, force: bool) -> Result<bool> {
+        self.exit_with(&EXIT_SIGNALS, force)
+    }
+
+    /// Exit process gracefully by sending a given sequence of signals.
+    ///
+    /// Signals are sent one by one,
+    /// after each one it waits [PtyProcess::set_terminate_approach_delay] and checks whether the child is terminated.
+    ///
+    /// If "force" is `true` then moves onto SIGKILL.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, Signal};
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.set_terminate_approach_delay(Duration::from_secs(5));
+    /// process.exit_with(&[Signal::SIGTERM], true).unwrap();
+    /// ```
+    pub fn exit_with(&mut self, signals: &[signal::Signal], force: bool) -> Result<bool> {
+        if !self.is_alive()? {
+            return Ok(true);
+        }
+
+        for &signal in signals {
+            if self.try_to_terminate(signal)? {
+                return Ok(true);
+            }
+        }
+
+        if !force {
+            return Ok(false);
+        }
+
+        self.try_to_terminate(SIGKILL)
+    }
+
+    /// Releases a [PtyProcess] without terminating a child process.
+    ///
+    /// By default a child is terminated when a [PtyProcess] is dropped.
+    ///
+    /// Notice that a master side of a PTY is closed,
+    /// so the child gets `SIGHUP` from the kernel unless it ignores it.
+    /// Also a child is not reaped so it becomes a zombie once it exits.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
+
+    /// Creates another handle to the same child process and PTY which doesn't own the child.
+    ///
+    /// The handle has its own descriptor of the PTY and its own read buffer,
+    /// so it may be passed to another subsystem (e.g to log an output).
+    /// Unlike the original [PtyProcess] dropping it doesn't terminate the child,
+    /// only the owner does it.
+    /// A status of a reaped child is shared by all handles.
+    ///
+    /// Notice that the handles read from the same PTY,
+    /// so each byte of an output is received by only one of them.
+    pub fn duplicate(&self) -> Result<Self> {
+        let file = self.master.get_file_handle()?;
+
+        Ok(Self {
+            master: self.master.clone(),
+            stream: Stream::with_capacity(self.stream_capacity, file),
+            child_pid: self.child_pid,
+            eof_char: self.eof_char,
+            intr_char: self.intr_char,
+            terminate_approach_delay: self.terminate_approach_delay,
+            interact_poll_interval: self.interact_poll_interval,
+            exit_status: self.exit_status.clone(),
+            detached: true,
+            stdin_taken: false,
+            stdout_taken: false,
+            stream_capacity: self.stream_capacity,
+            stderr: None,
+            initial_termios: Mutex::new(self.initial_termios()),
+            #[cfg(feature = "sync")]
+            utf8_tail: Vec::new(),
+            #[cfg(feature = "tokio")]
+            tokio_stream: None,
+            #[cfg(all(feature = "utmp", target_os = "linux"))]
+            utmp_entry: Mutex::new(None),
+        })
+    }
+
+    /// Checks whether the handle owns a child process,
+    /// i.e. terminates it once dropped.
+    ///
+    /// Handles created by [PtyProcess::duplicate] don't own a child.
+    pub fn owns_child(&self) -> bool {
+        !self.detached
+    }
+
+    /// Converts a [PtyProcess] into an owned descriptor of a PTY master.
+    ///
+    /// A child process keeps running and it's not terminated or reaped by the library anymore,
+    /// a caller is responsible for it (e.g. `waitpid` must be called on [PtyProcess::pid] taken beforehand).
+    /// The returned descriptor is the only one of the master left
+    /// (unless there are handles created by [PtyProcess::duplicate]),
+    /// so the child gets `SIGHUP` once it's closed.
+    ///
+    /// Data already read to an internal buffer is lost.
+    ///
+    /// ```no_run
+    /// use nix::sys::wait::waitpid;
+    /// use ptyprocess::PtyProcess;
+    /// use std::process::Command;
+    ///
+    /// let process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// let pid = process.pid();
+    /// let fd = process.into_owned_fd().unwrap();
+    ///
+    /// drop(fd);
+    /// waitpid(pid, None).unwrap();
+    /// ```
+    pub fn into_owned_fd(mut self) -> Result<OwnedFd> {
+        let fd = fcntl(self.master.as_raw_fd(), FcntlArg::F_DUPFD_CLOEXEC(0))?;
+        self.detached = true;
+
+        // SAFETY: the descriptor was just created and is not owned by anything else.
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    fn get_term_char_or(&self, char: SpecialCharacterIndices, default: u8) -> u8 {
+        match get_term_char(self.master.as_raw_fd(), char) {
+            Ok(c) if c != 0 && c != libc::_POSIX_VDISABLE => c,
+            _ => default,
+        }
+    }
+
+    // a status cache shared with a reaper, so a status reaped by it is known to all handles
+    #[cfg(feature = "sigchld")]
+    pub(crate) fn shared_exit_status(&self) -> Arc<Mutex<Option<WaitStatus>>> {
+        self.exit_status.clone()
+    }
+
+    /// Calls `waitpid` unless a child was already reaped and caches a status of a reaped child.
+    fn reap(&self, flags: Option<wait::WaitPidFlag>) -> Result<WaitStatus> {
+        if let Some(status) = *self.exit_status.lock().unwrap() {
+            return Ok(status);
+        }
+
+        // A lock is not held by a blocking call,
+        // so it doesn't block concurrent checks of a status.
+        match waitpid(self.child_pid, flags) {
+            Ok(status @ WaitStatus::Exited(..)) | Ok(status @ WaitStatus::Signaled(..)) => {
+                *self.exit_status.lock().unwrap() = Some(status);
+                Ok(status)
+            }
+            Ok(status) => Ok(status),
+            // the child might be reaped by a concurrent call
+            Err(err) => match *self.exit_status.lock().unwrap() {
+                Some(status) => Ok(status),
+                None => Err(PtyError::from(err)),
+            },
+        }
+    }
+
+    fn try_to_terminate(&mut self, signal: signal::Signal) -> Result<bool> {
+        self.kill(signal)?;
+        thread::sleep(self.terminate_approach_delay);
+
+        self.is_alive().map(|is_alive| !is_alive)
+    }
+}
+
+#[cfg(feature = "sync")]
+impl PtyProcess {
+    /// Send text to child's `STDIN`.
+    ///
+    /// To write bytes you can use a [std::io::Write] operations instead.
+    pub fn send<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        self.stream.write_all(s.as_ref().as_bytes())
+    }
+
+    /// Send a line to child's `STDIN`.
+    ///
+    /// It uses a platform default [LineEnding].
+    pub fn send_line<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        self.send_line_with(s, LineEnding::default())
+    }
+
+    /// Send a line to child's `STDIN` terminated by a given line ending.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{LineEnding, PtyProcess};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.send_line_with("Hello World", LineEnding::CrLf).unwrap();
+    /// ```
+    pub fn send_line_with<S: AsRef<str>>(&mut self, s: S, ending: LineEnding) -> io::Result<()> {
+        let bufs = &mut [
+            std::io::IoSlice::new(s.as_ref().as_bytes()),
+            std::io::IoSlice::new(ending.as_bytes()),
+            std::io::IoSlice::new(&[]), // we need to add a empty one as it may be not written.
+        ];
 
         let _ = self.write_vectored(bufs)?;
         self.flush()?;
@@ -341,40 +1548,1303 @@ impl PtyProcess {
         Ok(())
     }
 
+    /// Send bytes to a child one by one with a delay between them, as if they were typed.
+    ///
+    /// Some programs misbehave if input arrives too fast (e.g they drop bursts)
+    /// or parse it character by character.
+    /// Each byte is flushed right away.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.send_throttled(b"hello\n", Duration::from_millis(50)).unwrap();
+    /// ```
+    pub fn send_throttled(&mut self, bytes: &[u8], delay: Duration) -> io::Result<()> {
+        for (i, byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                thread::sleep(delay);
+            }
+
+            self.stream.write_all(std::slice::from_ref(byte))?;
+            self.stream.flush()?;
+        }
+
+        Ok(())
+    }
+
+    /// Send controll character to a child process.
+    ///
+    /// You must be carefull passing a char or &str as an argument.
+    /// If you pass an unexpected controll you'll get a error.
+    /// So it may be better to use [ControlCode].
+    ///
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, ControlCode};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.send_control(ControlCode::EndOfText); // sends CTRL^C
+    /// process.send_control('C'); // sends CTRL^C
+    /// process.send_control("^C"); // sends CTRL^C
+    /// ```
+    pub fn send_control(&mut self, code: impl TryInto<ControlCode>) -> io::Result<()> {
+        let code = code.try_into().map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "Failed to parse a control character")
+        })?;
+        self.stream.write_all(&[code.into()])
+    }
+
+    /// Send an ANSI escape sequence to a child process.
+    ///
+    /// ```no_run
+    /// use ptyprocess::{AnsiSequence, PtyProcess};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.send_ansi(AnsiSequence::CursorTo(1, 10)).unwrap();
+    /// ```
+    pub fn send_ansi(&mut self, seq: AnsiSequence) -> io::Result<()> {
+        self.stream.write_all(&seq.to_bytes())
+    }
+
+    /// Send a raw control byte to a child process.
+    ///
+    /// Any C0 control byte (`0x00`..=`0x1f`) or `DEL` (`0x7f`) is written as is.
+    /// It returns an error with [io::ErrorKind::InvalidInput] kind for other bytes.
+    pub fn send_raw_control(&mut self, byte: u8) -> io::Result<()> {
+        if !(byte < 0x20 || byte == 0x7f) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:#04x} is not a control character", byte),
+            ));
+        }
+
+        self.stream.write_all(&[byte])
+    }
+
+    /// Send `EOF` indicator to a child process.
+    ///
+    /// Often `eof` char handled as it would be a CTRL-C.
+    ///
+    /// Notice that it only writes the `VEOF` character (`^D` by default), it doesn't close anything.
+    /// A reader in canonical mode gets `EOF` from it,
+    /// but in raw mode (e.g an editor) it's received as a regular byte.
+    ///
+    /// There's no way to close only a writing direction of a PTY, as it's a single descriptor
+    /// (`shutdown(2)` works only on sockets).
+    /// Closing the master hangs up the terminal instead:
+    /// the child gets `SIGHUP` and its output can't be read anymore.
+    pub fn send_eof(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[self.eof_char])
+    }
+
+    /// Send `INTR` indicator to a child process.
+    ///
+    /// Often `intr` char handled as it would be a CTRL-D.
+    pub fn send_intr(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[self.intr_char])
+    }
+
+    /// Send `STOP` (XOFF) indicator to a child process.
+    ///
+    /// It suspends an output of a child if `IXON` is set.
+    /// A character is taken from terminal settings, `^S` is used if it's not set.
+    pub fn send_stop(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSTOP, DEFAULT_VSTOP_CHAR);
+        self.stream.write_all(&[c])
+    }
+
+    /// Send `START` (XON) indicator to a child process.
+    ///
+    /// It resumes an output of a child suspended by [PtyProcess::send_stop].
+    /// A character is taken from terminal settings, `^Q` is used if it's not set.
+    pub fn send_start(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSTART, DEFAULT_VSTART_CHAR);
+        self.stream.write_all(&[c])
+    }
+
+    /// Send `QUIT` indicator to a child process.
+    ///
+    /// A terminal sends `SIGQUIT` to a foreground process group on it, which usually produces a core dump.
+    /// A character is taken from terminal settings, `^\` is used if it's not set.
+    pub fn send_quit(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VQUIT, DEFAULT_VQUIT_CHAR);
+        self.stream.write_all(&[c])
+    }
+
+    /// Send `SUSP` indicator to a child process.
+    ///
+    /// A terminal sends `SIGTSTP` to a foreground process group on it.
+    /// A character is taken from terminal settings, `^Z` is used if it's not set.
+    pub fn send_susp(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSUSP, DEFAULT_VSUSP_CHAR);
+        self.stream.write_all(&[c])
+    }
+
+    /// Sets or clears `O_NONBLOCK` on a PTY master.
+    ///
+    /// In a non-blocking mode [Read] and [Write] operations return
+    /// an error with [io::ErrorKind::WouldBlock] kind instead of blocking.
+    /// `try_*` operations behave the same in both modes,
+    /// they temporarily make the descriptor non-blocking only if it's blocking.
+    ///
+    /// The mode is shared by all handles of the PTY,
+    /// including ones returned by [PtyProcess::get_pty_handle] and [PtyProcess::split].
+    /// The PTY is blocking by default.
+    pub fn set_nonblocking(&mut self, on: bool) -> Result<()> {
+        self.stream.set_nonblocking(on).map_err(PtyError::from)
+    }
+
+    /// Sets a timeout of blocking reads from a child.
+    ///
+    /// A read which doesn't get any data within the timeout returns
+    /// an error with [io::ErrorKind::TimedOut] kind, so it doesn't block forever.
+    /// A read returns as soon as any data is available, so it may return less than was asked.
+    /// `None` turns the timeout off, which is the default.
+    ///
+    /// The timeout is waited by `poll(2)` before each read of the PTY.
+    /// `VMIN` and `VTIME` terminal settings can't be used for it,
+    /// as they affect only reads of a child from the slave side and never reads of the master.
+    ///
+    /// It applies only to reads of `&mut PtyProcess`,
+    /// not to reads of `&PtyProcess` or of handles returned by [PtyProcess::get_pty_handle].
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{io::{self, Read}, process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.set_read_timeout(Some(Duration::from_millis(500)));
+    ///
+    /// let mut buf = [0; 128];
+    /// let err = process.read(&mut buf).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    /// ```
+    pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+        self.stream.set_read_timeout(timeout)
+    }
+
+    /// Reads all bytes which are currently available without blocking.
+    ///
+    /// Unlike [std::io::Read::read_to_end] it doesn't wait for `EOF`,
+    /// it returns as soon as there's nothing to read.
+    pub fn drain(&mut self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.drain_into(&mut data)?;
+        Ok(data)
+    }
+
+    /// Reads all bytes which are currently available without blocking and appends them to `out`.
+    ///
+    /// It's the same as [PtyProcess::drain] but lets a caller reuse a buffer.
+    /// It returns an amount of bytes appended.
+    pub fn drain_into(&mut self, out: &mut Vec<u8>) -> io::Result<usize> {
+        let len = out.len();
+        let mut buf = [0; 512];
+        while let Some(n) = self.try_read(&mut buf)? {
+            if n == 0 {
+                break;
+            }
+
+            out.extend_from_slice(&buf[..n]);
+        }
+
+        Ok(out.len() - len)
+    }
+
+    /// Reads all bytes until `EOF` into a buffer preallocated for `hint` bytes.
+    ///
+    /// It's the same as [std::io::Read::read_to_end] but a buffer doesn't grow incrementally
+    /// if the size of an output is roughly known in advance.
+    /// Reads larger than an internal buffer bypass it and go straight to the preallocated one.
+    pub fn read_to_vec(&mut self, hint: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(hint);
+        self.stream.read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Returns an iterator over lines of child's output.
+    ///
+    /// A PTY translates `\n` to `\r\n` by default, so unlike [std::io::BufRead::lines]
+    /// both `\n` and `\r\n` are stripped from a line.
+    /// The last line is returned even if it's not terminated.
+    /// The iterator ends on `EOF` and blocks while waiting for a line.
+    ///
+    /// A line which is not valid UTF-8 is not skipped,
+    /// invalid sequences are replaced with `U+FFFD` as [String::from_utf8_lossy] does.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("ls")).unwrap();
+    /// for line in process.lines() {
+    ///     println!("{}", line.unwrap());
+    /// }
+    /// ```
+    pub fn lines(&mut self) -> impl Iterator<Item = io::Result<String>> + '_ {
+        std::iter::from_fn(move || {
+            let mut buf = Vec::new();
+            match self.stream.read_until(b'\n', &mut buf) {
+                Ok(0) => None,
+                Ok(_) => {
+                    let mut line = String::from_utf8_lossy(&buf).into_owned();
+                    if line.ends_with('\n') {
+                        line.pop();
+                        if line.ends_with('\r') {
+                            line.pop();
+                        }
+                    }
+
+                    Some(Ok(line))
+                }
+                Err(err) => Some(Err(err)),
+            }
+        })
+    }
+
+    /// Reads all bytes which are currently available without blocking and decodes them as UTF-8.
+    ///
+    /// Invalid sequences are replaced with `U+FFFD` as [String::from_utf8_lossy] does.
+    /// But an incomplete sequence at the end is not considered invalid,
+    /// it's kept and decoded once the rest of it is read by a next call.
+    pub fn read_available_string(&mut self) -> io::Result<String> {
+        let mut data = std::mem::take(&mut self.utf8_tail);
+        data.extend(self.drain()?);
+
+        let mut s = String::with_capacity(data.len());
+        let mut rest = data.as_slice();
+        loop {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    s.push_str(valid);
+                    break;
+                }
+                Err(err) => {
+                    let (valid, invalid) = rest.split_at(err.valid_up_to());
+                    s.push_str(std::str::from_utf8(valid).expect("valid UTF-8"));
+
+                    match err.error_len() {
+                        Some(len) => {
+                            s.push(char::REPLACEMENT_CHARACTER);
+                            rest = &invalid[len..];
+                        }
+                        None => {
+                            self.utf8_tail = invalid.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(s)
+    }
+
+    /// Reads bytes until a `delim` byte is found or a timeout is reached.
+    ///
+    /// The `delim` byte is included in the result.
+    /// If a timeout is reached or `EOF` is found, the bytes read so far are returned.
+    /// If the timeout is `None` it waits until `delim` or `EOF`.
+    ///
+    /// Unlike [std::io::BufRead::read_until] it never blocks longer than the timeout.
+    pub fn read_until_timeout(
+        &mut self,
+        delim: u8,
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.read_until_into(delim, &mut buf, timeout)?;
+        Ok(buf)
+    }
+
+    /// Reads bytes until a `delim` byte is found or a timeout is reached appending them to `out`.
+    ///
+    /// It's the same as [PtyProcess::read_until_timeout] but lets a caller reuse a buffer.
+    /// It returns an amount of bytes appended.
+    pub fn read_until_into(
+        &mut self,
+        delim: u8,
+        out: &mut Vec<u8>,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let len = out.len();
+        let now = time::Instant::now();
+        while timeout.is_none() || now.elapsed() < timeout.unwrap() {
+            match self.try_read_byte()? {
+                Some(Some(b)) => {
+                    out.push(b);
+                    if b == delim {
+                        break;
+                    }
+                }
+                Some(None) => break,
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+
+        Ok(out.len() - len)
+    }
+
+    /// Reads the exact number of bytes required to fill `buf` unless a timeout is reached.
+    ///
+    /// It returns an error with [io::ErrorKind::TimedOut] kind if the timeout is reached
+    /// and [io::ErrorKind::UnexpectedEof] if `EOF` is found before `buf` is filled.
+    /// In both cases bytes which were read are kept in the beginning of `buf`,
+    /// the error message tells how many of them there are.
+    ///
+    /// Unlike [std::io::Read::read_exact] it never blocks longer than the timeout.
+    pub fn read_exact_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<()> {
+        let mut filled = 0;
+        let now = time::Instant::now();
+        while filled < buf.len() {
+            if now.elapsed() >= timeout {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    format!("read {} of {} bytes before a timeout", filled, buf.len()),
+                ));
+            }
+
+            match self.try_read(&mut buf[filled..])? {
+                Some(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("read {} of {} bytes before EOF", filled, buf.len()),
+                    ))
+                }
+                Some(n) => filled += n,
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a child doesn't produce any output for a given duration.
+    ///
+    /// It returns `true` if nothing was available to read during the whole `duration`,
+    /// and `false` as soon as some data appears.
+    /// The data is not consumed so it can be read afterwards.
+    ///
+    /// It may be used to detect that a child is idle (e.g a prompt is stabilized).
+    pub fn expect_silence(&mut self, duration: Duration) -> io::Result<bool> {
+        let now = time::Instant::now();
+        loop {
+            match self.stream.try_fill_buf()? {
+                Some(buf) if !buf.is_empty() => return Ok(false),
+                _ => {}
+            }
+
+            if now.elapsed() >= duration {
+                return Ok(true);
+            }
+
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    /// Reads bytes until a `needle` sequence is found or a timeout is reached.
+    ///
+    /// It returns all bytes read including the `needle`,
+    /// or `None` if the timeout was reached or `EOF` was found before the `needle`.
+    /// If the timeout is `None` it waits until `needle` or `EOF`.
+    ///
+    /// Bytes are consumed one by one (which is cheap as reads are buffered),
+    /// so nothing after the `needle` is consumed.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("sh")).unwrap();
+    /// let output = process.read_until_match(b"$ ", Some(Duration::from_secs(1))).unwrap();
+    /// ```
+    pub fn read_until_match(
+        &mut self,
+        needle: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let found = self.wait_for_any(&[needle], timeout)?;
+        Ok(found.map(|(_, buf)| buf))
+    }
+
+    /// Reads bytes until one of `needles` is found or a timeout is reached.
+    ///
+    /// It returns an index of a found needle and all bytes read including it,
+    /// or `None` if the timeout was reached or `EOF` was found before any needle.
+    /// If several needles end at the same byte the first one in the list is returned.
+    /// If the timeout is `None` it waits until a needle or `EOF`.
+    ///
+    /// Nothing after the needle is consumed.
+    /// An empty needle matches immediately without reading anything.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("sh")).unwrap();
+    /// let prompts: [&[u8]; 2] = [b"$ ", b"# "];
+    /// let (index, output) = process
+    ///     .wait_for_any(&prompts, Some(Duration::from_secs(1)))
+    ///     .unwrap()
+    ///     .unwrap();
+    /// ```
+    pub fn wait_for_any(
+        &mut self,
+        needles: &[&[u8]],
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<(usize, Vec<u8>)>> {
+        if let Some(index) = needles.iter().position(|needle| needle.is_empty()) {
+            return Ok(Some((index, Vec::new())));
+        }
+
+        let mut buf = Vec::new();
+
+        let now = time::Instant::now();
+        loop {
+            let data = match self.stream.try_fill_buf()? {
+                Some([]) => return Ok(None),
+                Some(data) => data,
+                None => {
+                    let timeout = match timeout {
+                        Some(timeout) => match timeout.checked_sub(now.elapsed()) {
+                            Some(left) if !left.is_zero() => Some(left),
+                            _ => return Ok(None),
+                        },
+                        None => None,
+                    };
+
+                    self.wait_readable(timeout)?;
+                    continue;
+                }
+            };
+
+            // bytes are checked one by one so nothing after a needle is consumed
+            let mut consumed = data.len();
+            let mut found = None;
+            for (i, &b) in data.iter().enumerate() {
+                buf.push(b);
+                if let Some(index) = find_needle(&buf, needles) {
+                    consumed = i + 1;
+                    found = Some(index);
+                    break;
+                }
+            }
+            self.stream.consume(consumed);
+
+            if let Some(index) = found {
+                return Ok(Some((index, buf)));
+            }
+
+            if matches!(timeout, Some(timeout) if now.elapsed() >= timeout) {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Blocks until a PTY is ready to be read or a timeout is passed.
+    fn wait_readable(&self, timeout: Option<Duration>) -> io::Result<()> {
+        let timeout = timeout.map_or(-1, |timeout| {
+            timeout.as_millis().min(i32::MAX as u128) as i32
+        });
+        let mut fds = [PollFd::new(self.master.as_raw_fd(), PollFlags::POLLIN)];
+        match poll(&mut fds, timeout) {
+            Ok(_) | Err(Error::Sys(Errno::EINTR)) => Ok(()),
+            Err(err) => Err(nix_error_to_io(err)),
+        }
+    }
+
+    /// Sends a line to a child and reads its output until a `prompt` is found or a timeout is reached.
+    ///
+    /// It returns the output between the line and the `prompt`.
+    /// If the line is echoed back (e.g echo is turned on) the echo is not included.
+    ///
+    /// It returns an error with [io::ErrorKind::TimedOut] kind if the timeout is reached
+    /// and [io::ErrorKind::UnexpectedEof] if `EOF` is found before the `prompt`.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::{process::Command, time::Duration};
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("sh")).unwrap();
+    /// let output = process
+    ///     .send_line_expect("echo Hello World", b"$ ", Some(Duration::from_secs(1)))
+    ///     .unwrap();
+    /// ```
+    pub fn send_line_expect(
+        &mut self,
+        line: &str,
+        prompt: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<u8>> {
+        self.send_line(line)?;
+
+        let now = time::Instant::now();
+        let mut output = match self.read_until_match(prompt, timeout)? {
+            Some(output) => output,
+            None if matches!(timeout, Some(timeout) if now.elapsed() >= timeout) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "a prompt is not found before a timeout",
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "a prompt is not found before EOF",
+                ))
+            }
+        };
+
+        output.truncate(output.len() - prompt.len());
+        strip_echo(&mut output, line);
+
+        Ok(output)
+    }
+
+    /// Interact gives control of the child process to the interactive user (the
+    /// human at the keyboard).
+    ///
+    /// Returns a status of a process ater interactions.
+    /// Why it's crusial to return a status is after check of is_alive the actuall
+    /// status might be gone.
+    ///
+    /// Keystrokes are sent to the child process, and
+    /// the `stdout` and `stderr` output of the child process is printed.
+    ///
+    /// When the user types the `escape_character` this method will return control to a running process.
+    /// The escape_character will not be transmitted.
+    /// The default for escape_character is entered as `Ctrl-]`, the very same as BSD telnet.
+    ///
+    /// This simply echos the child `stdout` and `stderr` to the real `stdout` and
+    /// it echos the real `stdin` to the child `stdin`.
+    pub fn interact(&mut self) -> io::Result<WaitStatus> {
+        self.interact_with_escape(ControlCode::GroupSeparator)
+    }
+
+    /// Interact gives control of the child process to the interactive user,
+    /// the same way as [PtyProcess::interact] does.
+    ///
+    /// The difference is that control is returned back when the user types the given `escape` character.
+    pub fn interact_with_escape(&mut self, escape: ControlCode) -> io::Result<WaitStatus> {
+        // flush buffers
+        self.flush()?;
+
+        let guard = InteractGuard::new(self.master.as_raw_fd(), true).map_err(nix_error_to_io)?;
+
+        let result = self._interact(escape.into(), None);
+
+        guard.restore().map_err(nix_error_to_io)?;
+
+        result
+    }
+
+    /// Interact gives control of the child process to the interactive user,
+    /// the same way as [PtyProcess::interact] does,
+    /// and writes a copy of all the relayed data to `log`.
+    ///
+    /// Each chunk is written on its own line with escaped non-printable characters.
+    /// A line starts with `<` if it was read from a child and with `>` if it was typed by the user.
+    ///
+    /// ```text
+    /// > "ls\r"
+    /// < "ls\r\nCargo.toml  src\r\n"
+    /// ```
+    pub fn interact_with_logger<L: Write>(&mut self, mut log: L) -> io::Result<WaitStatus> {
+        // flush buffers
+        self.flush()?;
+
+        let guard = InteractGuard::new(self.master.as_raw_fd(), true).map_err(nix_error_to_io)?;
+
+        let result = self._interact(ControlCode::GroupSeparator.into(), Some(&mut log));
+
+        guard.restore().map_err(nix_error_to_io)?;
+
+        result
+    }
+
+    /// Interact gives control of the child process to a given input and output,
+    /// the same way as [PtyProcess::interact] does with the real `stdin` and `stdout`.
+    ///
+    /// It may be used to bridge a PTY to a socket or to drive it from an in-memory buffer.
+    ///
+    /// The `input` is expected to be non-blocking,
+    /// it must return a `WouldBlock` error if there's nothing to read.
+    /// Otherwise child's output won't be relayed while waiting for input.
+    /// Interaction is stopped when `input` reaches `EOF` or the escape character `Ctrl-]` is read.
+    ///
+    /// A terminal mode of `input` is not changed.
+    pub fn interact_io<R: Read, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+    ) -> io::Result<WaitStatus> {
+        self.interact_io_with_escape(input, output, ControlCode::GroupSeparator)
+    }
+
+    /// Interact gives control of the child process to a given input and output,
+    /// the same way as [PtyProcess::interact_io] does.
+    ///
+    /// The difference is that interaction is stopped when the given `escape` character is read.
+    pub fn interact_io_with_escape<R: Read, W: Write>(
+        &mut self,
+        input: R,
+        output: W,
+        escape: ControlCode,
+    ) -> io::Result<WaitStatus> {
+        // flush buffers
+        self.flush()?;
+
+        let guard = InteractGuard::new(self.master.as_raw_fd(), false).map_err(nix_error_to_io)?;
+
+        let result = self._interact_io(input, output, escape.into(), None, None);
+
+        guard.restore().map_err(nix_error_to_io)?;
+
+        result
+    }
+
+    fn _interact(&mut self, escape: u8, log: Option<&mut dyn Write>) -> io::Result<WaitStatus> {
+        // it's crusial to make a DUP call here.
+        // If we don't actual stdin will be closed,
+        // And any interaction with it may cause errors.
+        //
+        // Why we don't use a `std::fs::File::try_clone` with a 0 fd?
+        // Because for some reason it actually doesn't make the same things as DUP does,
+        // eventhough a research showed that it should.
+        // https://github.com/zhiburt/expectrl/issues/7#issuecomment-884787229
+        let stdin_copy_fd = dup(STDIN_FILENO).map_err(nix_error_to_io)?;
+        let stdin = unsafe { std::fs::File::from_raw_fd(stdin_copy_fd) };
+        let stdin_stream = NonBlockingReader(Stream::new(stdin));
+
+        self._interact_io(
+            stdin_stream,
+            std::io::stdout(),
+            escape,
+            Some(stdin_copy_fd),
+            log,
+        )
+    }
+
+    /// An `input_fd` is a descriptor of `input` if any,
+    /// it's used to wake up once `input` is ready.
+    ///
+    /// If `log` is set all the relayed data is copied to it.
+    fn _interact_io<R: Read, W: Write>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+        escape: u8,
+        input_fd: Option<RawFd>,
+        mut log: Option<&mut dyn Write>,
+    ) -> io::Result<WaitStatus> {
+        let mut buf = [0; 512];
+        loop {
+            let status = self.status();
+            if !matches!(status, Ok(WaitStatus::StillAlive)) {
+                return status.map_err(io::Error::from);
+            }
+
+            let mut activity = false;
+
+            // it prints STDIN input as well,
+            // by echoing it.
+            //
+            // the setting must be set before calling the function.
+            if let Some(n) = self.try_read(&mut buf)? {
+                if n == 0 {
+                    // it might be too much to call a `status()` here,
+                    // do it just in case.
+                    return self.status().map_err(io::Error::from);
+                }
+
+                output.write_all(&buf[..n])?;
+                output.flush()?;
+                log_interact_chunk(&mut log, '<', &buf[..n])?;
+
+                activity = true;
+            }
+
+            match input.read(&mut buf) {
+                Ok(0) => {
+                    // it might be too much to call a `status()` here,
+                    // do it just in case.
+                    return self.status().map_err(io::Error::from);
+                }
+                Ok(n) => {
+                    let sent = buf[..n].iter().position(|&b| b == escape).unwrap_or(n);
+                    if sent > 0 {
+                        log_interact_chunk(&mut log, '>', &buf[..sent])?;
+                    }
+
+                    for i in 0..sent {
+                        self.write_all(&buf[i..i + 1])?;
+                    }
+
+                    if sent < n {
+                        // it might be too much to call a `status()` here,
+                        // do it just in case.
+                        return self.status().map_err(io::Error::from);
+                    }
+
+                    activity = true;
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {}
+                Err(err) => return Err(err),
+            }
+
+            if !activity {
+                self.wait_for_activity(input_fd)?;
+            }
+        }
+    }
+
+    /// Blocks until a PTY or an `input_fd` is ready to be read
+    /// or an interact poll interval is passed.
+    fn wait_for_activity(&self, input_fd: Option<RawFd>) -> io::Result<()> {
+        let timeout = self
+            .interact_poll_interval
+            .as_millis()
+            .min(i32::MAX as u128) as i32;
+
+        let mut fds = [
+            PollFd::new(self.master.as_raw_fd(), PollFlags::POLLIN),
+            PollFd::new(input_fd.unwrap_or(-1), PollFlags::POLLIN),
+        ];
+        // a negative fd is ignored by poll(2) but let's not rely on it.
+        let fds = match input_fd {
+            Some(_) => &mut fds[..],
+            None => &mut fds[..1],
+        };
+
+        match poll(fds, timeout) {
+            Ok(_) | Err(Error::Sys(Errno::EINTR)) => Ok(()),
+            Err(err) => Err(nix_error_to_io(err)),
+        }
+    }
+}
+
+/// Writes a chunk of relayed data on a separate line prefixed by a direction `marker`.
+#[cfg(feature = "sync")]
+fn log_interact_chunk(
+    log: &mut Option<&mut dyn Write>,
+    marker: char,
+    data: &[u8],
+) -> io::Result<()> {
+    match log {
+        Some(log) => {
+            writeln!(log, "{} \"{}\"", marker, data.escape_ascii())?;
+            log.flush()
+        }
+        None => Ok(()),
+    }
+}
+
+/// NonBlockingReader implements [Read] on top of a non-blocking `try_read`.
+///
+/// It returns a `WouldBlock` error if there's nothing to read.
+#[cfg(feature = "sync")]
+struct NonBlockingReader(Stream);
+
+#[cfg(feature = "sync")]
+impl Read for NonBlockingReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.try_read(buf)? {
+            Some(n) => Ok(n),
+            None => Err(io::Error::from(io::ErrorKind::WouldBlock)),
+        }
+    }
+}
+
+/// Reads directly from a PTY master without any buffering.
+///
+/// It's safe to read and write from different threads concurrently,
+/// reads and writes go to separate directions of a PTY and don't share any state.
+/// Concurrent reads from several threads are safe as well,
+/// but it's unspecified how data is distributed among them.
+///
+/// Data already buffered by a reader used through `&mut PtyProcess` (e.g by `read_line`)
+/// is not seen by it, so mixing the two may reorder data:
+/// bytes read directly are returned before ones which were buffered earlier,
+/// and buffered bytes are skipped unless they're read through `&mut PtyProcess` afterwards.
+/// A read blocks unless a non-blocking operation like `try_read` is running concurrently,
+/// in which case it may return a `WouldBlock` error.
+#[cfg(feature = "sync")]
+impl Read for &PtyProcess {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        unistd::read(self.master.as_raw_fd(), buf).map_err(nix_error_to_io)
+    }
+}
+
+/// Writes directly to a PTY master without any buffering.
+///
+/// See the [Read] implementation for `&PtyProcess` for thread-safety notes.
+#[cfg(feature = "sync")]
+impl Write for &PtyProcess {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        write(self.master.as_raw_fd(), buf).map_err(nix_error_to_io)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl PtyProcess {
+    /// Wait until a child process exits without blocking an executor.
+    ///
+    /// Unlike [PtyProcess::wait] it doesn't block a thread,
+    /// the status is polled every 100 milliseconds using a timer instead.
+    ///
+    /// If the child was already reaped its cached status is returned.
+    pub async fn wait_async(&self) -> Result<WaitStatus> {
+        loop {
+            match self.status()? {
+                WaitStatus::StillAlive => {
+                    async_io::Timer::after(Duration::from_millis(100)).await;
+                }
+                status => return Ok(status),
+            }
+        }
+    }
+
+    /// Terminates a child process without blocking an executor and releases a [PtyProcess].
+    ///
+    /// It's an async version of [PtyProcess::exit],
+    /// the same signals are sent but a delay between them is awaited by a timer.
+    /// Dropping a [PtyProcess] blocks a thread while a child is terminated,
+    /// so it's better to be called inside an async runtime.
+    ///
+    /// If the child could not be terminated it's left running as if [PtyProcess::detach] was called.
+    ///
+    /// ```no_run
+    /// use ptyprocess::PtyProcess;
+    /// use std::process::Command;
+    ///
+    /// # futures_lite::future::block_on(async {
+    /// let process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// assert!(process.shutdown(true).await.unwrap());
+    /// # })
+    /// ```
+    pub async fn shutdown(mut self, force: bool) -> io::Result<bool> {
+        // there's nothing left to do on drop
+        self.detached = true;
+
+        #[cfg(all(feature = "utmp", target_os = "linux"))]
+        let _ = self.unregister_utmp();
+
+        if !self.is_alive()? {
+            return Ok(true);
+        }
+
+        let signals = EXIT_SIGNALS.iter().copied();
+        let signals = signals.chain(if force { Some(SIGKILL) } else { None });
+        for signal in signals {
+            self.kill(signal)?;
+            async_io::Timer::after(self.terminate_approach_delay).await;
+
+            if !self.is_alive()? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Send text to child's `STDIN`.
+    ///
+    /// To write bytes you can use a [std::io::Write] operations instead.
+    pub async fn send<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        self.stream.write_all(s.as_ref().as_bytes()).await
+    }
+
+    /// Send a line to child's `STDIN`.
+    ///
+    /// It uses a platform default [LineEnding].
+    pub async fn send_line<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
+        self.send_line_with(s, LineEnding::default()).await
+    }
+
+    /// Send a line to child's `STDIN` terminated by a given line ending.
+    pub async fn send_line_with<S: AsRef<str>>(
+        &mut self,
+        s: S,
+        ending: LineEnding,
+    ) -> io::Result<()> {
+        self.write_all(s.as_ref().as_bytes()).await?;
+        self.write_all(ending.as_bytes()).await?;
+        self.flush().await?;
+
+        Ok(())
+    }
+
+    /// Send bytes to a child one by one with a delay between them, as if they were typed.
+    ///
+    /// The delay is awaited by a timer, so it doesn't block an executor.
+    /// See a sync version for details.
+    pub async fn send_throttled(&mut self, bytes: &[u8], delay: Duration) -> io::Result<()> {
+        for (i, byte) in bytes.iter().enumerate() {
+            if i > 0 {
+                async_io::Timer::after(delay).await;
+            }
+
+            self.stream.write_all(std::slice::from_ref(byte)).await?;
+            self.stream.flush().await?;
+        }
+
+        Ok(())
+    }
+
     /// Send controll character to a child process.
     ///
     /// You must be carefull passing a char or &str as an argument.
     /// If you pass an unexpected controll you'll get a error.
     /// So it may be better to use [ControlCode].
     ///
-    /// ```no_run
-    /// use ptyprocess::{PtyProcess, ControlCode};
-    /// use std::process::Command;
+    /// ```no_run
+    /// use ptyprocess::{PtyProcess, ControlCode};
+    /// use std::process::Command;
+    ///
+    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
+    /// process.send_control(ControlCode::EndOfText); // sends CTRL^C
+    /// process.send_control('C'); // sends CTRL^C
+    /// process.send_control("^C"); // sends CTRL^C
+    /// ```
+    pub async fn send_control(&mut self, code: impl TryInto<ControlCode>) -> io::Result<()> {
+        let code = code.try_into().map_err(|_| {
+            io::Error::new(io::ErrorKind::Other, "Failed to parse a control character")
+        })?;
+        self.stream.write_all(&[code.into()]).await
+    }
+
+    /// Send an ANSI escape sequence to a child process.
+    pub async fn send_ansi(&mut self, seq: AnsiSequence) -> io::Result<()> {
+        self.stream.write_all(&seq.to_bytes()).await
+    }
+
+    /// Send a raw control byte to a child process.
+    ///
+    /// Any C0 control byte (`0x00`..=`0x1f`) or `DEL` (`0x7f`) is written as is.
+    /// It returns an error with [io::ErrorKind::InvalidInput] kind for other bytes.
+    pub async fn send_raw_control(&mut self, byte: u8) -> io::Result<()> {
+        if !(byte < 0x20 || byte == 0x7f) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{:#04x} is not a control character", byte),
+            ));
+        }
+
+        self.stream.write_all(&[byte]).await
+    }
+
+    /// Send `EOF` indicator to a child process.
+    ///
+    /// Often `eof` char handled as it would be a CTRL-C.
+    ///
+    /// Notice that it only writes the `VEOF` character (`^D` by default), it doesn't close anything.
+    /// A reader in canonical mode gets `EOF` from it,
+    /// but in raw mode (e.g an editor) it's received as a regular byte.
+    ///
+    /// There's no way to close only a writing direction of a PTY, as it's a single descriptor
+    /// (`shutdown(2)` works only on sockets).
+    /// Closing the master hangs up the terminal instead:
+    /// the child gets `SIGHUP` and its output can't be read anymore.
+    pub async fn send_eof(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[self.eof_char]).await
+    }
+
+    /// Send `INTR` indicator to a child process.
+    ///
+    /// Often `intr` char handled as it would be a CTRL-D.
+    pub async fn send_intr(&mut self) -> io::Result<()> {
+        self.stream.write_all(&[self.intr_char]).await
+    }
+
+    /// Send `STOP` (XOFF) indicator to a child process.
+    ///
+    /// It suspends an output of a child if `IXON` is set.
+    /// A character is taken from terminal settings, `^S` is used if it's not set.
+    pub async fn send_stop(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSTOP, DEFAULT_VSTOP_CHAR);
+        self.stream.write_all(&[c]).await
+    }
+
+    /// Send `START` (XON) indicator to a child process.
+    ///
+    /// It resumes an output of a child suspended by [PtyProcess::send_stop].
+    /// A character is taken from terminal settings, `^Q` is used if it's not set.
+    pub async fn send_start(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSTART, DEFAULT_VSTART_CHAR);
+        self.stream.write_all(&[c]).await
+    }
+
+    /// Send `QUIT` indicator to a child process.
+    ///
+    /// A terminal sends `SIGQUIT` to a foreground process group on it, which usually produces a core dump.
+    /// A character is taken from terminal settings, `^\` is used if it's not set.
+    pub async fn send_quit(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VQUIT, DEFAULT_VQUIT_CHAR);
+        self.stream.write_all(&[c]).await
+    }
+
+    /// Send `SUSP` indicator to a child process.
+    ///
+    /// A terminal sends `SIGTSTP` to a foreground process group on it.
+    /// A character is taken from terminal settings, `^Z` is used if it's not set.
+    pub async fn send_susp(&mut self) -> io::Result<()> {
+        let c = self.get_term_char_or(SpecialCharacterIndices::VSUSP, DEFAULT_VSUSP_CHAR);
+        self.stream.write_all(&[c]).await
+    }
+
+    /// Reads all bytes which are currently available without waiting.
+    ///
+    /// Unlike [futures_lite::AsyncReadExt::read_to_end] it doesn't wait for `EOF`,
+    /// it returns as soon as there's nothing to read.
+    pub async fn drain(&mut self) -> io::Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.drain_into(&mut data).await?;
+        Ok(data)
+    }
+
+    /// Reads all bytes which are currently available without waiting and appends them to `out`.
+    ///
+    /// It's the same as [PtyProcess::drain] but lets a caller reuse a buffer.
+    /// It returns an amount of bytes appended.
+    pub async fn drain_into(&mut self, out: &mut Vec<u8>) -> io::Result<usize> {
+        let len = out.len();
+        let mut buf = [0; 512];
+        while let Some(n) = self.try_read(&mut buf).await? {
+            if n == 0 {
+                break;
+            }
+
+            out.extend_from_slice(&buf[..n]);
+        }
+
+        Ok(out.len() - len)
+    }
+
+    /// Reads all bytes until `EOF` into a buffer preallocated for `hint` bytes.
+    ///
+    /// It's the same as [futures_lite::AsyncReadExt::read_to_end] but a buffer doesn't grow incrementally
+    /// if the size of an output is roughly known in advance.
+    pub async fn read_to_vec(&mut self, hint: usize) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(hint);
+        self.stream.read_to_end(&mut buf).await?;
+        Ok(buf)
+    }
+
+    /// Reads bytes until a `delim` byte is found or a timeout is reached.
+    ///
+    /// The `delim` byte is included in the result.
+    /// If a timeout is reached or `EOF` is found, the bytes read so far are returned.
+    /// If the timeout is `None` it waits until `delim` or `EOF`.
+    pub async fn read_until_timeout(
+        &mut self,
+        delim: u8,
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.read_until_into(delim, &mut buf, timeout).await?;
+        Ok(buf)
+    }
+
+    /// Reads bytes until a `delim` byte is found or a timeout is reached appending them to `out`.
+    ///
+    /// It's the same as [PtyProcess::read_until_timeout] but lets a caller reuse a buffer.
+    /// It returns an amount of bytes appended.
+    pub async fn read_until_into(
+        &mut self,
+        delim: u8,
+        out: &mut Vec<u8>,
+        timeout: Option<Duration>,
+    ) -> io::Result<usize> {
+        let len = out.len();
+        let deadline = timeout.map(|timeout| time::Instant::now() + timeout);
+        loop {
+            match self.try_read_byte().await? {
+                Some(Some(b)) => {
+                    out.push(b);
+                    if b == delim {
+                        break;
+                    }
+                }
+                Some(None) => break,
+                None => {
+                    if self.fill_buf_until(deadline).await?.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(out.len() - len)
+    }
+
+    /// Reads the exact number of bytes required to fill `buf` unless a timeout is reached.
+    ///
+    /// It returns an error with [io::ErrorKind::TimedOut] kind if the timeout is reached
+    /// and [io::ErrorKind::UnexpectedEof] if `EOF` is found before `buf` is filled.
+    /// In both cases bytes which were read are kept in the beginning of `buf`,
+    /// the error message tells how many of them there are.
+    pub async fn read_exact_timeout(
+        &mut self,
+        buf: &mut [u8],
+        timeout: Duration,
+    ) -> io::Result<()> {
+        let mut filled = 0;
+        let deadline = time::Instant::now() + timeout;
+        while filled < buf.len() {
+            match self.try_read(&mut buf[filled..]).await? {
+                Some(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        format!("read {} of {} bytes before EOF", filled, buf.len()),
+                    ))
+                }
+                Some(n) => filled += n,
+                None => {
+                    if self.fill_buf_until(Some(deadline)).await?.is_none() {
+                        return Err(io::Error::new(
+                            io::ErrorKind::TimedOut,
+                            format!("read {} of {} bytes before a timeout", filled, buf.len()),
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Checks that a child doesn't produce any output for a given duration.
+    ///
+    /// It returns `true` if nothing was available to read during the whole `duration`,
+    /// and `false` as soon as some data appears.
+    /// The data is not consumed so it can be read afterwards.
+    pub async fn expect_silence(&mut self, duration: Duration) -> io::Result<bool> {
+        let deadline = time::Instant::now() + duration;
+        match self.fill_buf_until(Some(deadline)).await? {
+            Some(0) => {
+                // there will be no output after EOF
+                async_io::Timer::at(deadline).await;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+            None => Ok(true),
+        }
+    }
+
+    /// Reads bytes until a `needle` sequence is found or a timeout is reached.
+    ///
+    /// It returns all bytes read including the `needle`,
+    /// or `None` if the timeout was reached or `EOF` was found before the `needle`.
+    /// If the timeout is `None` it waits until `needle` or `EOF`.
+    ///
+    /// Nothing after the `needle` is consumed.
+    pub async fn read_until_match(
+        &mut self,
+        needle: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<Vec<u8>>> {
+        let found = self.wait_for_any(&[needle], timeout).await?;
+        Ok(found.map(|(_, buf)| buf))
+    }
+
+    /// Reads bytes until one of `needles` is found or a timeout is reached.
+    ///
+    /// It returns an index of a found needle and all bytes read including it,
+    /// or `None` if the timeout was reached or `EOF` was found before any needle.
+    /// If several needles end at the same byte the first one in the list is returned.
+    /// If the timeout is `None` it waits until a needle or `EOF`.
     ///
-    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
-    /// process.send_control(ControlCode::EndOfText); // sends CTRL^C
-    /// process.send_control('C'); // sends CTRL^C
-    /// process.send_control("^C"); // sends CTRL^C
-    /// ```
-    pub fn send_control(&mut self, code: impl TryInto<ControlCode>) -> io::Result<()> {
-        let code = code.try_into().map_err(|_| {
-            io::Error::new(io::ErrorKind::Other, "Failed to parse a control character")
-        })?;
-        self.stream.write_all(&[code.into()])
+    /// Nothing after the needle is consumed.
+    /// An empty needle matches immediately without reading anything.
+    pub async fn wait_for_any(
+        &mut self,
+        needles: &[&[u8]],
+        timeout: Option<Duration>,
+    ) -> io::Result<Option<(usize, Vec<u8>)>> {
+        let mut buf = Vec::new();
+        let deadline = timeout.map(|timeout| time::Instant::now() + timeout);
+        loop {
+            if let Some(index) = find_needle(&buf, needles) {
+                return Ok(Some((index, buf)));
+            }
+
+            match self.try_read_byte().await? {
+                Some(Some(b)) => buf.push(b),
+                Some(None) => return Ok(None),
+                None => {
+                    if self.fill_buf_until(deadline).await?.is_none() {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
     }
 
-    /// Send `EOF` indicator to a child process.
+    /// Sends a line to a child and reads its output until a `prompt` is found or a timeout is reached.
     ///
-    /// Often `eof` char handled as it would be a CTRL-C.
-    pub fn send_eof(&mut self) -> io::Result<()> {
-        self.stream.write_all(&[self.eof_char])
+    /// It returns the output between the line and the `prompt`.
+    /// If the line is echoed back (e.g echo is turned on) the echo is not included.
+    ///
+    /// It returns an error with [io::ErrorKind::TimedOut] kind if the timeout is reached
+    /// and [io::ErrorKind::UnexpectedEof] if `EOF` is found before the `prompt`.
+    pub async fn send_line_expect(
+        &mut self,
+        line: &str,
+        prompt: &[u8],
+        timeout: Option<Duration>,
+    ) -> io::Result<Vec<u8>> {
+        self.send_line(line).await?;
+
+        let now = time::Instant::now();
+        let mut output = match self.read_until_match(prompt, timeout).await? {
+            Some(output) => output,
+            None if matches!(timeout, Some(timeout) if now.elapsed() >= timeout) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "a prompt is not found before a timeout",
+                ))
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "a prompt is not found before EOF",
+                ))
+            }
+        };
+
+        output.truncate(output.len() - prompt.len());
+        strip_echo(&mut output, line);
+
+        Ok(output)
     }
 
-    /// Send `INTR` indicator to a child process.
+    /// Waits until there's something to read or a deadline is reached.
     ///
-    /// Often `intr` char handled as it would be a CTRL-D.
-    pub fn send_intr(&mut self) -> io::Result<()> {
-        self.stream.write_all(&[self.intr_char])
+    /// It returns an amount of buffered bytes (0 on `EOF`)
+    /// or `None` if the deadline was reached first.
+    /// Nothing is lost if the deadline is reached as filling a buffer may be safely cancelled.
+    async fn fill_buf_until(
+        &mut self,
+        deadline: Option<time::Instant>,
+    ) -> io::Result<Option<usize>> {
+        let fill = async { self.stream.fill_buf().await.map(|buf| Some(buf.len())) };
+        match deadline {
+            Some(deadline) => {
+                let timer = async {
+                    async_io::Timer::at(deadline).await;
+                    Ok(None)
+                };
+                future::or(fill, timer).await
+            }
+            None => fill.await,
+        }
     }
 
     /// Interact gives control of the child process to the interactive user (the
@@ -393,47 +2863,149 @@ impl PtyProcess {
     ///
     /// This simply echos the child `stdout` and `stderr` to the real `stdout` and
     /// it echos the real `stdin` to the child `stdin`.
-    pub fn interact(&mut self) -> io::Result<WaitStatus> {
+    pub async fn interact(&mut self) -> io::Result<WaitStatus> {
+        self.interact_with_escape(ControlCode::GroupSeparator).await
+    }
+
+    /// Interact gives control of the child process to the interactive user,
+    /// the same way as [PtyProcess::interact] does.
+    ///
+    /// The difference is that control is returned back when the user types the given `escape` character.
+    pub async fn interact_with_escape(&mut self, escape: ControlCode) -> io::Result<WaitStatus> {
         // flush buffers
-        self.flush()?;
+        self.flush().await?;
 
-        let origin_pty_echo = self.get_echo().map_err(nix_error_to_io)?;
-        self.set_echo(true).map_err(nix_error_to_io)?;
+        let guard = InteractGuard::new(self.master.as_raw_fd(), true).map_err(nix_error_to_io)?;
 
-        // verify: possible controlling fd can be stdout and stderr as well?
-        // https://stackoverflow.com/questions/35873843/when-setting-terminal-attributes-via-tcsetattrfd-can-fd-be-either-stdout
-        let isatty_in = isatty(STDIN_FILENO).map_err(nix_error_to_io)?;
+        let result = self._interact(escape.into()).await;
 
-        // tcgetattr issues error if a provided fd is not a tty,
-        // so we run set_raw only when it's a tty.
-        //
-        // todo: simplify.
-        if isatty_in {
-            let origin_stdin_flags = termios::tcgetattr(STDIN_FILENO).map_err(nix_error_to_io)?;
-            set_raw(STDIN_FILENO).map_err(nix_error_to_io)?;
+        guard.restore().map_err(nix_error_to_io)?;
 
-            let result = self._interact();
+        result
+    }
 
-            termios::tcsetattr(
-                STDIN_FILENO,
-                termios::SetArg::TCSAFLUSH,
-                &origin_stdin_flags,
-            )
-            .map_err(nix_error_to_io)?;
+    /// Interact gives control of the child process to a given input and output,
+    /// the same way as [PtyProcess::interact] does with the real `stdin` and `stdout`.
+    ///
+    /// It may be used to bridge a PTY to a socket or to drive it from an in-memory buffer.
+    ///
+    /// Child's output is relayed while waiting for input.
+    /// Interaction is stopped when `input` reaches `EOF` or the escape character `Ctrl-]` is read.
+    ///
+    /// A terminal mode of `input` is not changed.
+    pub async fn interact_io<R, W>(&mut self, input: R, output: W) -> io::Result<WaitStatus>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        self.interact_io_with_escape(input, output, ControlCode::GroupSeparator)
+            .await
+    }
 
-            self.set_echo(origin_pty_echo).map_err(nix_error_to_io)?;
+    /// Interact gives control of the child process to a given input and output,
+    /// the same way as [PtyProcess::interact_io] does.
+    ///
+    /// The difference is that interaction is stopped when the given `escape` character is read.
+    pub async fn interact_io_with_escape<R, W>(
+        &mut self,
+        input: R,
+        output: W,
+        escape: ControlCode,
+    ) -> io::Result<WaitStatus>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        // flush buffers
+        self.flush().await?;
 
-            result
-        } else {
-            let result = self._interact();
+        let guard = InteractGuard::new(self.master.as_raw_fd(), false).map_err(nix_error_to_io)?;
+
+        let result = self._interact_io(input, output, escape.into()).await;
+
+        guard.restore().map_err(nix_error_to_io)?;
+
+        result
+    }
+
+    async fn _interact_io<R, W>(
+        &mut self,
+        mut input: R,
+        mut output: W,
+        escape: u8,
+    ) -> io::Result<WaitStatus>
+    where
+        R: AsyncRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        enum Event {
+            Output(io::Result<usize>),
+            Input(io::Result<usize>),
+            Tick,
+        }
+
+        let mut output_buf = [0; 512];
+        let mut input_buf = [0; 512];
+        loop {
+            let status = self.status();
+            if !matches!(status, Ok(WaitStatus::StillAlive)) {
+                return status.map_err(io::Error::from);
+            }
 
-            self.set_echo(origin_pty_echo).map_err(nix_error_to_io)?;
+            // a status is checked on a tick,
+            // as a child may exit while the PTY is still kept open (e.g by a background job).
+            //
+            // a race is not biased so a busy child doesn't starve an input.
+            let interval = self.interact_poll_interval;
+            let stream = &mut self.stream;
+            let event = future::race(
+                async { Event::Output(stream.read(&mut output_buf).await) },
+                future::or(
+                    async { Event::Input(input.read(&mut input_buf).await) },
+                    async {
+                        async_io::Timer::after(interval).await;
+                        Event::Tick
+                    },
+                ),
+            )
+            .await;
+
+            match event {
+                // it prints STDIN input as well,
+                // by echoing it.
+                //
+                // the setting must be set before calling the function.
+                Event::Output(Ok(0)) | Event::Input(Ok(0)) => {
+                    // it might be too much to call a `status()` here,
+                    // do it just in case.
+                    return self.status().map_err(io::Error::from);
+                }
+                Event::Output(Ok(n)) => {
+                    output.write_all(&output_buf[..n]).await?;
+                    output.flush().await?;
+                }
+                Event::Input(Ok(n)) => {
+                    let sent = input_buf[..n]
+                        .iter()
+                        .position(|&b| b == escape)
+                        .unwrap_or(n);
+                    for i in 0..sent {
+                        self.write_all(&input_buf[i..i + 1]).await?;
+                    }
 
-            result
+                    if sent < n {
+                        // it might be too much to call a `status()` here,
+                        // do it just in case.
+                        return self.status().map_err(io::Error::from);
+                    }
+                }
+                Event::Output(Err(err)) | Event::Input(Err(err)) => return Err(err),
+                Event::Tick => {}
+            }
         }
     }
 
-    fn _interact(&mut self) -> io::Result<WaitStatus> {
+    async fn _interact(&mut self, escape: u8) -> io::Result<WaitStatus> {
         // it's crusial to make a DUP call here.
         // If we don't actual stdin will be closed,
         // And any interaction with it may cause errors.
@@ -442,7 +3014,8 @@ impl PtyProcess {
         // Because for some reason it actually doesn't make the same things as DUP does,
         // eventhough a research showed that it should.
         // https://github.com/zhiburt/expectrl/issues/7#issuecomment-884787229
-        let stdin_copy_fd = dup(STDIN_FILENO).map_err(nix_error_to_io)?;
+        let stdin_copy_fd = dup(0).map_err(nix_error_to_io)?;
+
         let stdin = unsafe { std::fs::File::from_raw_fd(stdin_copy_fd) };
         let mut stdin_stream = Stream::new(stdin);
 
@@ -450,232 +3023,762 @@ impl PtyProcess {
         loop {
             let status = self.status();
             if !matches!(status, Ok(WaitStatus::StillAlive)) {
-                return status.map_err(nix_error_to_io);
+                return status.map_err(io::Error::from);
             }
 
-            let mut activity = false;
-
             // it prints STDIN input as well,
             // by echoing it.
             //
             // the setting must be set before calling the function.
-            if let Some(n) = self.try_read(&mut buf)? {
-                if n == 0 {
-                    // it might be too much to call a `status()` here,
-                    // do it just in case.
-                    return self.status().map_err(nix_error_to_io);
-                }
+            let mut activity = false;
 
+            if let Some(n) = self.try_read(&mut buf).await? {
                 std::io::stdout().write_all(&buf[..n])?;
                 std::io::stdout().flush()?;
-
                 activity = true;
             }
 
-            if let Some(n) = stdin_stream.try_read(&mut buf)? {
-                if n == 0 {
-                    // it might be too much to call a `status()` here,
-                    // do it just in case.
-                    return self.status().map_err(nix_error_to_io);
-                }
-
+            if let Some(n) = stdin_stream.try_read(&mut buf).await? {
+                activity = true;
                 for i in 0..n {
-                    // Ctrl-]
-                    if buf[i] == ControlCode::GroupSeparator.into() {
+                    if buf[i] == escape {
                         // it might be too much to call a `status()` here,
                         // do it just in case.
-                        return self.status().map_err(nix_error_to_io);
+                        return self.status().map_err(io::Error::from);
                     }
 
-                    self.write_all(&buf[i..i + 1])?;
+                    self.write_all(&buf[i..i + 1]).await?;
                 }
-
-                activity = true;
             }
 
             if !activity {
-                std::thread::sleep(std::time::Duration::from_millis(10));
+                async_io::Timer::after(self.interact_poll_interval).await;
             }
         }
     }
 }
 
-#[cfg(feature = "async")]
-impl PtyProcess {
-    /// Send text to child's `STDIN`.
+/// Returns an index of the first needle a buffer ends with.
+///
+/// It's called after each byte is read, so a match is never missed by checking only a suffix.
+fn find_needle(buf: &[u8], needles: &[&[u8]]) -> Option<usize> {
+    needles.iter().position(|needle| buf.ends_with(needle))
+}
+
+/// Removes an echo of a sent line from the beginning of an output if it's there.
+fn strip_echo(output: &mut Vec<u8>, line: &str) {
+    if let Some(rest) = output.strip_prefix(line.as_bytes()) {
+        let echo_len = if rest.starts_with(b"\r\n") {
+            line.len() + 2
+        } else if rest.starts_with(b"\n") || rest.starts_with(b"\r") {
+            line.len() + 1
+        } else {
+            0
+        };
+
+        output.drain(..echo_len);
+    }
+}
+
+/// SpawnEvent is a step of spawning a child process reported by [PtyProcess::spawn_with_observer].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SpawnEvent {
+    /// A child process was forked.
+    Forked(Pid),
+    /// A child process failed to set itself up or to execute a command.
     ///
-    /// To write bytes you can use a [std::io::Write] operations instead.
-    pub async fn send<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
-        self.stream.write_all(s.as_ref().as_bytes()).await
+    /// The child exits right after it and an error is returned from a spawn call.
+    ExecFailed(Errno),
+    /// A command was executed and a [PtyProcess] is ready to be used.
+    Ready,
+}
+
+/// Program is what's run in a child process after a PTY is set up.
+enum Program<'a> {
+    Command(Command),
+    Fn(Box<dyn FnOnce() -> i32 + 'a>),
+}
+
+/// InteractGuard changes terminal settings for an interaction
+/// and restores them once it's dropped.
+///
+/// So the user's terminal is left usable even if an interaction is interrupted by an error or a panic.
+struct InteractGuard {
+    pty_fd: RawFd,
+    pty_echo: bool,
+    stdin_guard: Option<RawModeGuard>,
+    restored: bool,
+}
+
+impl InteractGuard {
+    /// Turns on echo on a PTY, and if `raw_stdin` is set turns on a raw mode on `stdin`.
+    fn new(pty_fd: RawFd, raw_stdin: bool) -> nix::Result<Self> {
+        let pty_echo = termios::tcgetattr(pty_fd)?
+            .local_flags
+            .contains(termios::LocalFlags::ECHO);
+        set_echo(pty_fd, true)?;
+
+        let mut guard = Self {
+            pty_fd,
+            pty_echo,
+            stdin_guard: None,
+            restored: false,
+        };
+
+        // verify: possible controlling fd can be stdout and stderr as well?
+        // https://stackoverflow.com/questions/35873843/when-setting-terminal-attributes-via-tcsetattrfd-can-fd-be-either-stdout
+        //
+        // tcgetattr issues error if a provided fd is not a tty,
+        // so we run set_raw only when it's a tty.
+        if raw_stdin && isatty(STDIN_FILENO)? {
+            guard.stdin_guard = Some(RawModeGuard::set(STDIN_FILENO)?);
+        }
+
+        Ok(guard)
     }
 
-    /// Send a line to child's `STDIN`.
-    pub async fn send_line<S: AsRef<str>>(&mut self, s: S) -> io::Result<()> {
-        #[cfg(windows)]
-        const LINE_ENDING: &[u8] = b"\r\n";
-        #[cfg(not(windows))]
-        const LINE_ENDING: &[u8] = b"\n";
+    /// Restores settings reporting an error if any.
+    fn restore(mut self) -> nix::Result<()> {
+        self.restored = true;
+        self._restore()
+    }
 
-        let _ = self.write_all(s.as_ref().as_bytes()).await?;
-        let _ = self.write_all(LINE_ENDING).await?;
-        self.flush().await?;
+    fn _restore(&mut self) -> nix::Result<()> {
+        // try to restore everything even if something fails
+        let stdin_result = match self.stdin_guard.take() {
+            Some(mut guard) => {
+                guard.restored = true;
+                guard.reset()
+            }
+            None => Ok(()),
+        };
+        let echo_result = set_echo(self.pty_fd, self.pty_echo);
 
-        Ok(())
+        stdin_result.and(echo_result)
+    }
+}
+
+impl Drop for InteractGuard {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = self._restore();
+        }
     }
+}
 
-    /// Send controll character to a child process.
+/// RawModeGuard puts a terminal in a raw mode and restores its settings once it's dropped.
+///
+/// It's what [PtyProcess::interact] does with `stdin`,
+/// so it may be used to build a custom interaction loop.
+/// Settings are restored even if a loop is interrupted by an error or a panic.
+///
+/// ```no_run
+/// use ptyprocess::RawModeGuard;
+///
+/// let guard = RawModeGuard::new(0).unwrap();
+/// // read keystrokes one by one
+/// guard.restore().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RawModeGuard {
+    fd: RawFd,
+    termios: termios::Termios,
+    restored: bool,
+}
+
+impl RawModeGuard {
+    /// Saves settings of a terminal and puts it in a raw mode.
     ///
-    /// You must be carefull passing a char or &str as an argument.
-    /// If you pass an unexpected controll you'll get a error.
-    /// So it may be better to use [ControlCode].
+    /// It returns an error if `fd` is not a terminal.
+    pub fn new(fd: RawFd) -> Result<Self> {
+        Self::set(fd).map_err(PtyError::from)
+    }
+
+    /// Returns settings the terminal had before it was put in a raw mode.
+    pub fn termios(&self) -> &termios::Termios {
+        &self.termios
+    }
+
+    /// Restores settings of the terminal reporting an error if any.
+    pub fn restore(mut self) -> Result<()> {
+        self.restored = true;
+        self.reset().map_err(PtyError::from)
+    }
+
+    fn set(fd: RawFd) -> nix::Result<Self> {
+        let termios = termios::tcgetattr(fd)?;
+        set_raw(fd)?;
+
+        Ok(Self {
+            fd,
+            termios,
+            restored: false,
+        })
+    }
+
+    fn reset(&self) -> nix::Result<()> {
+        termios::tcsetattr(self.fd, termios::SetArg::TCSAFLUSH, &self.termios)
+    }
+}
+
+impl Drop for RawModeGuard {
+    fn drop(&mut self) {
+        if !self.restored {
+            let _ = self.reset();
+        }
+    }
+}
+
+/// PtyProcessBuilder configures a [PtyProcess] before it's spawned.
+///
+/// All settings are applied inside a child before a command is executed,
+/// so a spawned program never observes the defaults.
+///
+/// ```no_run
+/// use ptyprocess::PtyProcessBuilder;
+/// use std::{process::Command, time::Duration};
+///
+/// let process = PtyProcessBuilder::new()
+///     .command(Command::new("cat"))
+///     .window_size(120, 40)
+///     .echo(true)
+///     .terminate_delay(Duration::from_millis(300))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct PtyProcessBuilder {
+    command: Option<Command>,
+    window_size: (u16, u16),
+    echo: bool,
+    terminate_approach_delay: Duration,
+    close_fds: bool,
+    controlling_tty: bool,
+    reset_signals: bool,
+    slave_flags: OFlag,
+    stream_capacity: usize,
+    process_group: Option<Pid>,
+    termios: Option<termios::Termios>,
+    split_stderr: bool,
+    current_dir: Option<PathBuf>,
+    // a PTY allocated beforehand by [Pty::open]
+    pty: Option<Master>,
+}
+
+impl PtyProcessBuilder {
+    /// Creates a builder with default settings.
     ///
-    /// ```no_run
-    /// use ptyprocess::{PtyProcess, ControlCode};
-    /// use std::process::Command;
+    /// The default terminal size is 80x24 and echo is turned off.
+    pub fn new() -> Self {
+        Self {
+            command: None,
+            window_size: (DEFAULT_TERM_COLS, DEFAULT_TERM_ROWS),
+            echo: false,
+            terminate_approach_delay: DEFAULT_TERMINATE_APPROACH_DELAY,
+            close_fds: true,
+            controlling_tty: true,
+            reset_signals: false,
+            slave_flags: OFlag::O_RDWR,
+            stream_capacity: stream::DEFAULT_CAPACITY,
+            process_group: None,
+            termios: None,
+            split_stderr: false,
+            current_dir: None,
+            pty: None,
+        }
+    }
+
+    /// Sets a command which will be spawned.
+    pub fn command(mut self, command: Command) -> Self {
+        self.command = Some(command);
+        self
+    }
+
+    /// Sets an initial terminal size.
+    pub fn window_size(mut self, cols: u16, rows: u16) -> Self {
+        self.window_size = (cols, rows);
+        self
+    }
+
+    /// Sets an initial echo setting.
+    pub fn echo(mut self, on: bool) -> Self {
+        self.echo = on;
+        self
+    }
+
+    /// Sets initial terminal settings.
     ///
-    /// let mut process = PtyProcess::spawn(Command::new("cat")).unwrap();
-    /// process.send_control(ControlCode::EndOfText); // sends CTRL^C
-    /// process.send_control('C'); // sends CTRL^C
-    /// process.send_control("^C"); // sends CTRL^C
-    /// ```
-    pub async fn send_control(&mut self, code: impl TryInto<ControlCode>) -> io::Result<()> {
-        let code = code.try_into().map_err(|_| {
-            io::Error::new(io::ErrorKind::Other, "Failed to parse a control character")
-        })?;
-        self.stream.write_all(&[code.into()]).await
+    /// They're applied before a command is executed instead of default settings of a PTY.
+    /// An echo setting is applied afterwards, so [PtyProcessBuilder::echo] takes precedence over them.
+    pub fn termios(mut self, flags: termios::Termios) -> Self {
+        self.termios = Some(flags);
+        self
     }
 
-    /// Send `EOF` indicator to a child process.
+    /// Sets a delay between termination attempts used by [PtyProcess::exit].
+    pub fn terminate_delay(mut self, delay: Duration) -> Self {
+        self.terminate_approach_delay = delay;
+        self
+    }
+
+    /// Sets whether descriptors inherited from a parent are closed in a child.
     ///
-    /// Often `eof` char handled as it would be a CTRL-C.
-    pub async fn send_eof(&mut self) -> io::Result<()> {
-        self.stream.write_all(&[self.eof_char]).await
+    /// By default all descriptors except standard streams are closed.
+    /// On Linux it's done by a single `close_range(2)` call,
+    /// on other systems each possible descriptor is closed one by one,
+    /// which may take a while if `OPEN_MAX` is large.
+    ///
+    /// If turned off, descriptors which don't have `FD_CLOEXEC` set are inherited by a child.
+    pub fn close_fds(mut self, on: bool) -> Self {
+        self.close_fds = on;
+        self
     }
 
-    /// Send `INTR` indicator to a child process.
+    /// Sets whether the PTY becomes a controlling terminal of a child.
     ///
-    /// Often `intr` char handled as it would be a CTRL-D.
-    pub async fn send_intr(&mut self) -> io::Result<()> {
-        self.stream.write_all(&[self.intr_char]).await
+    /// It's turned on by default.
+    /// If turned off a child is still started in a new session,
+    /// but it has no controlling terminal, so there's no job control
+    /// and control characters like `^C` don't generate signals.
+    pub fn controlling_tty(mut self, on: bool) -> Self {
+        self.controlling_tty = on;
+        self
     }
 
-    /// Interact gives control of the child process to the interactive user (the
-    /// human at the keyboard).
+    /// Sets whether signal dispositions and a signal mask are reset in a child.
     ///
-    /// Returns a status of a process ater interactions.
-    /// Why it's crusial to return a status is after check of is_alive the actuall
-    /// status might be gone.
+    /// A child inherits ignored signals and a signal mask of a parent,
+    /// which may surprise a spawned program (e.g a library in a parent ignores `SIGINT`).
+    /// If turned on all signals are set to their default actions
+    /// and unblocked before a command is executed, as a shell does.
     ///
-    /// Keystrokes are sent to the child process, and
-    /// the `stdout` and `stderr` output of the child process is printed.
+    /// It's turned off by default.
+    /// Notice that [std::process::Command] itself already resets `SIGPIPE` and the mask.
+    pub fn reset_signals(mut self, on: bool) -> Self {
+        self.reset_signals = on;
+        self
+    }
+
+    /// Sets flags used to open a PTY slave in a child.
     ///
-    /// When the user types the `escape_character` this method will return control to a running process.
-    /// The escape_character will not be transmitted.
-    /// The default for escape_character is entered as `Ctrl-]`, the very same as BSD telnet.
+    /// The slave becomes child's standard streams,
+    /// so the flags must give both read and write access (e.g `O_RDWR | O_NOCTTY`).
+    /// The default is `O_RDWR`.
     ///
-    /// This simply echos the child `stdout` and `stderr` to the real `stdout` and
-    /// it echos the real `stdin` to the child `stdin`.
-    pub async fn interact(&mut self) -> io::Result<WaitStatus> {
-        // flush buffers
-        self.flush().await?;
+    /// The PTY still becomes a controlling terminal of a child unless it's turned off by
+    /// [PtyProcessBuilder::controlling_tty], as it's set separately.
+    pub fn slave_open_flags(mut self, flags: OFlag) -> Self {
+        self.slave_flags = flags;
+        self
+    }
+
+    /// Puts a child into a given process group instead of a new session.
+    ///
+    /// By default a child is a leader of a new session and of a new process group.
+    /// If a group is set a child stays in a session of a parent and calls `setpgid(0, pgid)`,
+    /// so a job control shell can manage it as a part of a job.
+    /// A zero pid makes a child a leader of a new group, as `setpgid(2)` does.
+    /// A group must belong to the session of a parent.
+    ///
+    /// A process group can't be combined with a controlling terminal,
+    /// as only a session leader may acquire one.
+    /// So the PTY is never a controlling terminal of such a child and
+    /// [PtyProcessBuilder::controlling_tty] is ignored.
+    /// A child's controlling terminal (if any) is the one of a parent.
+    pub fn process_group(mut self, pgid: Pid) -> Self {
+        self.process_group = Some(pgid);
+        self
+    }
+
+    /// Sets whether child's `stderr` is connected to a separate pipe instead of the PTY.
+    ///
+    /// The output is available by [PtyProcess::stderr] so it's not mixed with `stdout`.
+    /// Notice that a child doesn't see a terminal on `stderr` anymore
+    /// (e.g `isatty(2)` is false and colors may be turned off),
+    /// and a pipe must be read, otherwise the child blocks once it's full.
+    ///
+    /// It's turned off by default.
+    pub fn split_stderr(mut self, on: bool) -> Self {
+        self.split_stderr = on;
+        self
+    }
+
+    /// Sets a working directory of a child process.
+    ///
+    /// The directory is changed by the child itself right before an exec.
+    /// A relative path is resolved against a working directory of a parent.
+    /// If the directory can't be changed the spawn fails with [PtyError::ChildSetup] error
+    /// at [SpawnStage::WorkingDirectory] stage.
+    ///
+    /// A directory set by [Command::current_dir] is honored too,
+    /// it's applied after this one by [Command] itself.
+    pub fn current_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Sets a capacity of a buffer used to read from a PTY.
+    ///
+    /// Notice that a larger buffer doesn't necessarily reduce an amount of system calls,
+    /// as a PTY itself may return data by small chunks (e.g on Linux a read rarely returns more than 4 KiB).
+    /// A smaller buffer may be used to reduce memory usage.
+    /// The default is 8 KiB.
+    ///
+    /// A capacity of 0 is raised to 1 byte, as an empty buffer would be taken for `EOF`.
+    pub fn stream_capacity(mut self, capacity: usize) -> Self {
+        self.stream_capacity = capacity.max(1);
+        self
+    }
+
+    /// Spawns a child process and create a [PtyProcess].
+    ///
+    /// It returns [PtyError::Spawn] error with [io::ErrorKind::InvalidInput] kind if a command wasn't set.
+    pub fn build(mut self) -> Result<PtyProcess> {
+        let command = match self.command.take() {
+            Some(command) => command,
+            None => {
+                return Err(PtyError::Spawn(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "a command is not set",
+                )))
+            }
+        };
 
-        let origin_pty_echo = self.get_echo().map_err(nix_error_to_io)?;
-        self.set_echo(true).map_err(nix_error_to_io)?;
+        self.spawn(Program::Command(command), false, None)
+            .map(|(process, _)| process)
+    }
 
-        // verify: possible controlling fd can be stdout and stderr as well?
-        // https://stackoverflow.com/questions/35873843/when-setting-terminal-attributes-via-tcsetattrfd-can-fd-be-either-stdout
-        let isatty_in = isatty(STDIN_FILENO).map_err(nix_error_to_io)?;
+    fn spawn(
+        self,
+        program: Program<'_>,
+        keep_slave: bool,
+        mut observer: Option<&mut dyn FnMut(SpawnEvent)>,
+    ) -> Result<(PtyProcess, Option<File>)> {
+        let Self {
+            window_size: (cols, rows),
+            echo,
+            terminate_approach_delay,
+            close_fds,
+            controlling_tty,
+            reset_signals,
+            slave_flags,
+            stream_capacity,
+            process_group,
+            termios: flags,
+            split_stderr,
+            current_dir,
+            pty,
+            ..
+        } = self;
+
+        let master = match pty {
+            Some(master) => master,
+            None => Master::open_unlocked().map_err(PtyError::spawn)?,
+        };
+
+        // The slave is opened before a fork so the PTY is never torn down
+        // even if the child closes all its copies of the slave right away.
+        // It's marked `FD_CLOEXEC` and a child closes its copy right after the fork,
+        // so it's not inherited even if other descriptors are kept.
+        let slave = if keep_slave {
+            let slave = master.open_slave().map_err(PtyError::spawn)?;
+            fcntl(slave.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+                .map_err(PtyError::spawn)?;
+            Some(slave)
+        } else {
+            None
+        };
+
+        let stderr_pipe = if split_stderr {
+            let (read, write) = pipe().map_err(PtyError::spawn)?;
+            // only a copy made by dup2 is inherited
+            for fd in [read, write] {
+                if let Err(err) = fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC)) {
+                    let _ = close(read);
+                    let _ = close(write);
+                    return Err(PtyError::spawn(err));
+                }
+            }
+            Some((read, write))
+        } else {
+            None
+        };
+        let close_stderr_pipe = || {
+            if let Some((read, write)) = stderr_pipe {
+                let _ = close(read);
+                let _ = close(write);
+            }
+        };
 
-        // tcgetattr issues error if a provided fd is not a tty,
-        // so we run set_raw only when it's a tty.
-        //
-        // todo: simplify.
-        if isatty_in {
-            let origin_stdin_flags = termios::tcgetattr(STDIN_FILENO).map_err(nix_error_to_io)?;
-            set_raw(STDIN_FILENO).map_err(nix_error_to_io)?;
+        // handle errors in child executions by pipe
+        let (exec_err_pipe_read, exec_err_pipe_write) = match pipe() {
+            Ok(pipe) => pipe,
+            Err(err) => {
+                close_stderr_pipe();
+                return Err(PtyError::spawn(err));
+            }
+        };
+
+        let fork = match unsafe { fork() } {
+            Ok(fork) => fork,
+            Err(err) => {
+                close_stderr_pipe();
+                let _ = close(exec_err_pipe_read);
+                let _ = close(exec_err_pipe_write);
+                return Err(PtyError::spawn(err));
+            }
+        };
+        match fork {
+            ForkResult::Child => {
+                // a closure run instead of a command is not covered by `FD_CLOEXEC`
+                drop(slave);
 
-            let result = self._interact().await;
+                let setup = || -> std::result::Result<(), (SpawnStage, nix::Error)> {
+                    let at = |stage| move |err| (stage, err);
 
-            termios::tcsetattr(
-                STDIN_FILENO,
-                termios::SetArg::TCSAFLUSH,
-                &origin_stdin_flags,
-            )
-            .map_err(nix_error_to_io)?;
+                    if reset_signals {
+                        reset_signal_handling().map_err(at(SpawnStage::ResetSignals))?;
+                    }
 
-            self.set_echo(origin_pty_echo).map_err(nix_error_to_io)?;
+                    let device = master.get_slave_name().map_err(at(SpawnStage::OpenSlave))?;
+                    let slave_fd = master
+                        .get_slave_fd(slave_flags)
+                        .map_err(at(SpawnStage::OpenSlave))?;
+                    drop(master);
 
-            result
-        } else {
-            let result = self._interact().await;
+                    if let Some(pgid) = process_group {
+                        setpgid(Pid::from_raw(0), pgid).map_err(at(SpawnStage::ProcessGroup))?;
+                    } else if controlling_tty {
+                        make_controlling_tty(&device, slave_fd)
+                            .map_err(at(SpawnStage::ControllingTty))?;
+                    } else {
+                        // The slave was opened before a new session is created,
+                        // so it doesn't become a controlling terminal.
+                        setsid().map_err(at(SpawnStage::ControllingTty))?;
+                    }
+                    redirect_std_streams(slave_fd).map_err(at(SpawnStage::RedirectStreams))?;
+                    if let Some((_, write)) = stderr_pipe {
+                        dup2(write, STDERR_FILENO).map_err(at(SpawnStage::RedirectStreams))?;
+                    }
 
-            self.set_echo(origin_pty_echo).map_err(nix_error_to_io)?;
+                    if let Some(flags) = &flags {
+                        termios::tcsetattr(STDIN_FILENO, termios::SetArg::TCSANOW, flags)
+                            .map_err(at(SpawnStage::TerminalSettings))?;
+                    }
+                    set_echo(STDIN_FILENO, echo).map_err(at(SpawnStage::TerminalSettings))?;
+                    set_term_size(STDIN_FILENO, cols, rows)
+                        .map_err(at(SpawnStage::TerminalSettings))?;
 
-            result
-        }
-    }
+                    if let Some(dir) = &current_dir {
+                        unistd::chdir(dir.as_path()).map_err(at(SpawnStage::WorkingDirectory))?;
+                    }
 
-    async fn _interact(&mut self) -> io::Result<WaitStatus> {
-        // it's crusial to make a DUP call here.
-        // If we don't actual stdin will be closed,
-        // And any interaction with it may cause errors.
-        //
-        // Why we don't use a `std::fs::File::try_clone` with a 0 fd?
-        // Because for some reason it actually doesn't make the same things as DUP does,
-        // eventhough a research showed that it should.
-        // https://github.com/zhiburt/expectrl/issues/7#issuecomment-884787229
-        let stdin_copy_fd = dup(0).map_err(nix_error_to_io)?;
+                    close(exec_err_pipe_read).map_err(at(SpawnStage::ErrorPipe))?;
+                    // close pipe on sucessfull exec
+                    fcntl(exec_err_pipe_write, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+                        .map_err(at(SpawnStage::ErrorPipe))?;
 
-        let stdin = unsafe { std::fs::File::from_raw_fd(stdin_copy_fd) };
-        let mut stdin_stream = Stream::new(stdin);
+                    // Do not allow child to inherit open file descriptors from parent
+                    if close_fds {
+                        close_all_fds_except([slave_fd, exec_err_pipe_write])
+                            .map_err(at(SpawnStage::CloseFds))?;
+                    }
 
-        let mut buf = [0; 512];
-        loop {
-            let status = self.status();
-            if !matches!(status, Ok(WaitStatus::StillAlive)) {
-                return status.map_err(nix_error_to_io);
-            }
+                    Ok(())
+                };
+
+                let (stage, code) = match setup() {
+                    Ok(()) => match program {
+                        Program::Command(mut command) => {
+                            let err = command.exec();
+                            (CHILD_STAGE_EXEC, err.raw_os_error().unwrap_or(-1))
+                        }
+                        Program::Fn(f) => {
+                            // There's no exec which would close the pipe,
+                            // so it's closed manually to notify the parent about a success.
+                            let _ = close(exec_err_pipe_write);
+
+                            // A panic must not unwind into a code copied from a parent,
+                            // and `process::exit` would run the parent's `atexit` handlers.
+                            let code = panic::catch_unwind(panic::AssertUnwindSafe(f))
+                                .unwrap_or(SPAWN_FN_PANIC_EXIT_CODE);
+                            unsafe { libc::_exit(code) }
+                        }
+                    },
+                    Err((stage, err)) => (stage.tag(), err.as_errno().map_or(-1, |e| e as i32)),
+                };
+
+                let mut pipe_buf = [0u8; 5];
+                pipe_buf[0] = stage;
+                pipe_buf[1..].copy_from_slice(&code.to_be_bytes());
+                let _ = write(exec_err_pipe_write, &pipe_buf);
 
-            // it prints STDIN input as well,
-            // by echoing it.
-            //
-            // the setting must be set before calling the function.
-            if let Some(n) = self.try_read(&mut buf).await? {
-                std::io::stdout().write_all(&buf[..n])?;
-                std::io::stdout().flush()?;
+                process::exit(code);
             }
-
-            if let Some(n) = stdin_stream.try_read(&mut buf).await? {
-                for i in 0..n {
-                    // Ctrl-]
-                    if buf[i] == ControlCode::GroupSeparator.into() {
-                        // it might be too much to call a `status()` here,
-                        // do it just in case.
-                        return self.status().map_err(nix_error_to_io);
+            ForkResult::Parent { child } => {
+                let mut notify = |event| {
+                    if let Some(observer) = observer.as_mut() {
+                        observer(event);
                     }
+                };
+                notify(SpawnEvent::Forked(child));
 
-                    self.write_all(&buf[i..i + 1]).await?;
+                let stderr = stderr_pipe.map(|(read, write)| {
+                    let _ = close(write);
+                    unsafe { File::from_raw_fd(read) }
+                });
+
+                close(exec_err_pipe_write).map_err(PtyError::spawn)?;
+
+                let mut pipe_buf = [0u8; 5];
+                let read = unistd::read(exec_err_pipe_read, &mut pipe_buf);
+                let _ = close(exec_err_pipe_read);
+                read.map_err(PtyError::spawn)?;
+
+                let code = i32::from_be_bytes([pipe_buf[1], pipe_buf[2], pipe_buf[3], pipe_buf[4]]);
+                if code != 0 {
+                    notify(SpawnEvent::ExecFailed(Errno::from_i32(code)));
+
+                    let err = io::Error::from_raw_os_error(code);
+                    return match pipe_buf[0] {
+                        CHILD_STAGE_EXEC => Err(PtyError::exec(err)),
+                        tag => match SpawnStage::from_tag(tag) {
+                            Some(stage) => Err(PtyError::ChildSetup(stage, err)),
+                            None => Err(PtyError::Spawn(err)),
+                        },
+                    };
                 }
+
+                // Some systems may work in this way? (not sure)
+                // that we need to set a terminal size in a parent.
+                set_term_size(master.as_raw_fd(), cols, rows).map_err(PtyError::spawn)?;
+
+                // Read control characters of the PTY itself rather than of our terminal,
+                // as they are the ones the child is actually sensitive to.
+                let eof_char = get_term_char(master.as_raw_fd(), SpecialCharacterIndices::VEOF)
+                    .unwrap_or(DEFAULT_VEOF_CHAR);
+                let intr_char = get_term_char(master.as_raw_fd(), SpecialCharacterIndices::VINTR)
+                    .unwrap_or(DEFAULT_INTR_CHAR);
+
+                let initial_termios =
+                    termios::tcgetattr(master.as_raw_fd()).map_err(PtyError::spawn)?;
+
+                let file = master.get_file_handle().map_err(PtyError::spawn)?;
+                let stream = Stream::with_capacity(stream_capacity, file);
+
+                let process = PtyProcess {
+                    master,
+                    stream,
+                    child_pid: child,
+                    eof_char,
+                    intr_char,
+                    terminate_approach_delay,
+                    interact_poll_interval: DEFAULT_INTERACT_POLL_INTERVAL,
+                    exit_status: Arc::new(Mutex::new(None)),
+                    detached: false,
+                    stdin_taken: false,
+                    stdout_taken: false,
+                    stream_capacity,
+                    stderr,
+                    initial_termios: Mutex::new(initial_termios),
+                    #[cfg(feature = "sync")]
+                    utf8_tail: Vec::new(),
+                    #[cfg(feature = "tokio")]
+                    tokio_stream: None,
+                    #[cfg(all(feature = "utmp", target_os = "linux"))]
+                    utmp_entry: Mutex::new(None),
+                };
+
+                notify(SpawnEvent::Ready);
+
+                Ok((process, slave))
             }
         }
     }
 }
 
-fn nix_error_to_io(err: nix::Error) -> io::Error {
-    match err.as_errno() {
-        Some(code) => io::Error::from_raw_os_error(code as _),
-        None => io::Error::new(
-            io::ErrorKind::Other,
-            "Unexpected error type conversion from nix to io",
-        ),
+impl Default for PtyProcessBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl PtyProcess {
+    fn tokio_stream(&mut self) -> io::Result<&mut TokioStream> {
+        if self.tokio_stream.is_none() {
+            let file = self.get_pty_handle()?;
+            self.tokio_stream = Some(TokioStream::new(file)?);
+        }
+
+        Ok(self.tokio_stream.as_mut().unwrap())
+    }
+}
+
+/// [tokio::io::AsyncRead] implementation which is driven by a tokio reactor.
+///
+/// The PTY is registered in a reactor on a first read or write,
+/// which must happen within a tokio runtime.
+/// The registration puts the PTY into a non-blocking mode,
+/// so it's better not to mix it with blocking operations.
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for PtyProcess {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        let stream = self.get_mut().tokio_stream()?;
+        std::pin::Pin::new(stream).poll_read(cx, buf)
+    }
+}
+
+/// [tokio::io::AsyncWrite] implementation which is driven by a tokio reactor.
+///
+/// See [tokio::io::AsyncRead] implementation for details.
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for PtyProcess {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        let stream = self.get_mut().tokio_stream()?;
+        std::pin::Pin::new(stream).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        let stream = self.get_mut().tokio_stream()?;
+        std::pin::Pin::new(stream).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        let stream = self.get_mut().tokio_stream()?;
+        std::pin::Pin::new(stream).poll_shutdown(cx)
     }
 }
 
 impl Drop for PtyProcess {
     fn drop(&mut self) {
+        if self.detached {
+            return;
+        }
+
+        // A panic in drop may abort a program,
+        // so errors are ignored as there's nothing we can do about them here.
         if let Ok(WaitStatus::StillAlive) = self.status() {
-            self.exit(true).unwrap();
+            let _ = self.exit(true);
         }
+
+        #[cfg(all(feature = "utmp", target_os = "linux"))]
+        let _ = self.unregister_utmp();
     }
 }
 
@@ -693,9 +3796,24 @@ impl DerefMut for PtyProcess {
     }
 }
 
-fn set_term_size(fd: i32, cols: u16, rows: u16) -> Result<()> {
-    ioctl_write_ptr_bad!(_set_window_size, libc::TIOCSWINSZ, winsize);
+impl AsRawFd for PtyProcess {
+    /// Returns a master fd of a PTY.
+    ///
+    /// Unlike [PtyProcess::get_pty_handle] it doesn't make a copy of the descriptor.
+    fn as_raw_fd(&self) -> RawFd {
+        self.master.as_raw_fd()
+    }
+}
+
+impl AsFd for PtyProcess {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the descriptor is owned by `self.master`,
+        // so it stays open for the lifetime of the borrow.
+        unsafe { BorrowedFd::borrow_raw(self.master.as_raw_fd()) }
+    }
+}
 
+fn set_term_size(fd: i32, cols: u16, rows: u16) -> nix::Result<()> {
     let size = winsize {
         ws_row: rows,
         ws_col: cols,
@@ -703,12 +3821,93 @@ fn set_term_size(fd: i32, cols: u16, rows: u16) -> Result<()> {
         ws_ypixel: 0,
     };
 
-    let _ = unsafe { _set_window_size(fd, &size) }?;
+    set_winsize(fd, &size)
+}
+
+fn get_term_size(fd: i32) -> nix::Result<(u16, u16)> {
+    get_winsize(fd).map(|size| (size.ws_col, size.ws_row))
+}
+
+fn set_winsize(fd: i32, size: &winsize) -> nix::Result<()> {
+    ioctl_write_ptr_bad!(_set_window_size, libc::TIOCSWINSZ, winsize);
+
+    let _ = unsafe { _set_window_size(fd, size) }?;
+
+    Ok(())
+}
+
+// Returns a size set by `$COLUMNS` and `$LINES`, which are looked up by a given function.
+fn get_env_winsize<F>(lookup: F) -> Result<(u16, u16)>
+where
+    F: Fn(&str) -> std::result::Result<String, std::env::VarError>,
+{
+    let cols = get_env_size("COLUMNS", &lookup)?;
+    let rows = get_env_size("LINES", &lookup)?;
+    Ok((cols, rows))
+}
+
+fn get_env_size<F>(var: &str, lookup: F) -> Result<u16>
+where
+    F: Fn(&str) -> std::result::Result<String, std::env::VarError>,
+{
+    let value = lookup(var).map_err(|err| {
+        PtyError::Winsize(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{}: {}", var, err),
+        ))
+    })?;
+
+    value.trim().parse().map_err(|err| {
+        PtyError::Winsize(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("{}: {}", var, err),
+        ))
+    })
+}
+
+fn set_break(fd: RawFd, on: bool) -> nix::Result<()> {
+    #[cfg(not(target_os = "macos"))]
+    ioctl_none_bad!(_set_break, libc::TIOCSBRK);
+    #[cfg(not(target_os = "macos"))]
+    ioctl_none_bad!(_clear_break, libc::TIOCCBRK);
+    // They're not defined in libc for macOS, it's _IO('t', 123) and _IO('t', 122).
+    #[cfg(target_os = "macos")]
+    ioctl_none_bad!(_set_break, 0x2000747b);
+    #[cfg(target_os = "macos")]
+    ioctl_none_bad!(_clear_break, 0x2000747a);
+
+    let _ = match on {
+        true => unsafe { _set_break(fd) }?,
+        false => unsafe { _clear_break(fd) }?,
+    };
+
+    Ok(())
+}
+
+fn push_to_input(fd: RawFd, bytes: &[u8]) -> nix::Result<()> {
+    #[cfg(not(target_os = "macos"))]
+    ioctl_write_ptr_bad!(_push_char, libc::TIOCSTI, u8);
+    // It's not defined in libc for macOS, it's _IOW('t', 114, char).
+    #[cfg(target_os = "macos")]
+    ioctl_write_ptr_bad!(_push_char, 0x80017472, u8);
+
+    for b in bytes {
+        let _ = unsafe { _push_char(fd, b) }?;
+    }
 
     Ok(())
 }
 
-fn get_term_size(fd: i32) -> Result<(u16, u16)> {
+fn get_pending_bytes(fd: RawFd) -> nix::Result<usize> {
+    nix::ioctl_read_bad!(_get_pending_bytes, libc::FIONREAD, libc::c_int);
+
+    let mut n = 0;
+    let _ = unsafe { _get_pending_bytes(fd, &mut n) }?;
+
+    Ok(n as usize)
+}
+
+fn get_winsize(fd: i32) -> nix::Result<winsize> {
     nix::ioctl_read_bad!(_get_window_size, libc::TIOCGWINSZ, winsize);
 
     let mut size = winsize {
@@ -720,40 +3919,135 @@ fn get_term_size(fd: i32) -> Result<(u16, u16)> {
 
     let _ = unsafe { _get_window_size(fd, &mut size) }?;
 
-    Ok((size.ws_col, size.ws_row))
+    Ok(size)
 }
 
+/// Pty is a PTY allocated before a process is spawned.
+///
+/// It allows to inspect a PTY (e.g its slave name) before deciding whether and what to run in it.
+///
+/// ```no_run
+/// use ptyprocess::Pty;
+/// use std::process::Command;
+///
+/// let pty = Pty::open().unwrap();
+/// println!("a slave is {}", pty.slave_name().unwrap());
+///
+/// let process = pty.spawn_into(Command::new("cat")).unwrap();
+/// ```
 #[derive(Debug)]
+pub struct Pty {
+    master: Master,
+}
+
+impl Pty {
+    /// Opens a new PTY master.
+    ///
+    /// Access to a slave is granted and it's unlocked (`grantpt(3)` and `unlockpt(3)`),
+    /// so the slave can be opened right away.
+    pub fn open() -> Result<Self> {
+        let master = Master::open_unlocked()?;
+        Ok(Self { master })
+    }
+
+    /// Returns a name of a slave device (e.g `/dev/pts/1`).
+    pub fn slave_name(&self) -> Result<String> {
+        self.master.get_slave_name().map_err(PtyError::from)
+    }
+
+    /// Returns a master descriptor.
+    ///
+    /// It's valid as long as the [Pty] is alive.
+    pub fn master_fd(&self) -> BorrowedFd<'_> {
+        self.as_fd()
+    }
+
+    /// Spawns a child process in the PTY and create a [PtyProcess].
+    ///
+    /// Default settings of a [PtyProcessBuilder] are used.
+    pub fn spawn_into(self, command: Command) -> Result<PtyProcess> {
+        PtyProcessBuilder {
+            pty: Some(self.master),
+            ..PtyProcessBuilder::new()
+        }
+        .command(command)
+        .build()
+    }
+}
+
+impl AsRawFd for Pty {
+    fn as_raw_fd(&self) -> RawFd {
+        self.master.as_raw_fd()
+    }
+}
+
+impl AsFd for Pty {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        // SAFETY: the descriptor is owned by the master and lives as long as it does
+        unsafe { BorrowedFd::borrow_raw(self.master.as_raw_fd()) }
+    }
+}
+
+/// Master is a PTY master descriptor shared by all handles of a [PtyProcess].
+///
+/// It's closed once the last handle is dropped.
+#[derive(Debug, Clone)]
 struct Master {
-    fd: PtyMaster,
+    fd: Arc<PtyMaster>,
 }
 
 impl Master {
-    fn open() -> Result<Self> {
+    fn open() -> nix::Result<Self> {
         let master_fd = posix_openpt(OFlag::O_RDWR)?;
-        Ok(Self { fd: master_fd })
+        // The master must never be inherited by a child,
+        // even if inherited descriptors are not closed in it.
+        fcntl(master_fd.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))?;
+
+        Ok(Self {
+            fd: Arc::new(master_fd),
+        })
     }
 
-    fn grant_slave_access(&self) -> Result<()> {
+    fn open_unlocked() -> nix::Result<Self> {
+        let master = Self::open()?;
+        master.grant_slave_access()?;
+        master.unlock_slave()?;
+        Ok(master)
+    }
+
+    fn grant_slave_access(&self) -> nix::Result<()> {
         grantpt(&self.fd)
     }
 
-    fn unlock_slave(&self) -> Result<()> {
+    fn unlock_slave(&self) -> nix::Result<()> {
         unlockpt(&self.fd)
     }
 
-    fn get_slave_name(&self) -> Result<String> {
+    fn get_slave_name(&self) -> nix::Result<String> {
         get_slave_name(&self.fd)
     }
 
-    fn get_slave_fd(&self) -> Result<RawFd> {
+    fn get_slave_fd(&self, flags: OFlag) -> nix::Result<RawFd> {
         let slave_name = self.get_slave_name()?;
-        let slave_fd = open(slave_name.as_str(), OFlag::O_RDWR, Mode::empty())?;
+        let slave_fd = open(slave_name.as_str(), flags, Mode::empty())?;
         Ok(slave_fd)
     }
 
-    fn get_file_handle(&self) -> Result<File> {
-        let fd = dup(self.as_raw_fd())?;
+    fn open_slave(&self) -> nix::Result<File> {
+        let slave_name = self.get_slave_name()?;
+        let fd = open(
+            slave_name.as_str(),
+            OFlag::O_RDWR | OFlag::O_NOCTTY,
+            Mode::empty(),
+        )?;
+        let file = unsafe { File::from_raw_fd(fd) };
+
+        Ok(file)
+    }
+
+    fn get_file_handle(&self) -> nix::Result<File> {
+        // unlike dup(2) it keeps the descriptor from being inherited
+        let fd = fcntl(self.as_raw_fd(), FcntlArg::F_DUPFD_CLOEXEC(0))?;
         let file = unsafe { File::from_raw_fd(fd) };
 
         Ok(file)
@@ -767,14 +4061,14 @@ impl AsRawFd for Master {
 }
 
 #[cfg(not(target_os = "macos"))]
-fn get_slave_name(fd: &PtyMaster) -> Result<String> {
+fn get_slave_name(fd: &PtyMaster) -> nix::Result<String> {
     nix::pty::ptsname_r(fd)
 }
 
 /// Getting a slave name on darvin platform
 /// https://blog.tarq.io/ptsname-on-osx-with-rust/
 #[cfg(target_os = "macos")]
-fn get_slave_name(fd: &PtyMaster) -> Result<String> {
+fn get_slave_name(fd: &PtyMaster) -> nix::Result<String> {
     use nix::libc::ioctl;
     use nix::libc::TIOCPTYGNAME;
     use std::ffi::CStr;
@@ -800,7 +4094,7 @@ fn get_slave_name(fd: &PtyMaster) -> Result<String> {
     }
 }
 
-fn redirect_std_streams(fd: RawFd) -> Result<()> {
+fn redirect_std_streams(fd: RawFd) -> nix::Result<()> {
     // If fildes2 is already a valid open file descriptor, it shall be closed first
 
     close(STDIN_FILENO)?;
@@ -815,20 +4109,108 @@ fn redirect_std_streams(fd: RawFd) -> Result<()> {
     Ok(())
 }
 
-fn set_echo(fd: RawFd, on: bool) -> Result<()> {
+/// Checks whether a child has exited, leaving it in a waitable state.
+fn has_exited(pid: Pid) -> nix::Result<bool> {
+    is_waitable(pid, libc::WEXITED)
+}
+
+/// Checks whether a child is stopped, leaving it in a waitable state.
+fn has_stopped(pid: Pid) -> nix::Result<bool> {
+    is_waitable(pid, libc::WSTOPPED)
+}
+
+/// Checks whether a child has changed to a given state (`WEXITED`, `WSTOPPED`)
+/// which wasn't waited for yet.
+fn is_waitable(pid: Pid, state: libc::c_int) -> nix::Result<bool> {
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    let flags = state | libc::WNOHANG | libc::WNOWAIT;
+    let ret = unsafe { libc::waitid(libc::P_PID, pid.as_raw() as libc::id_t, &mut info, flags) };
+    Errno::result(ret)?;
+
+    // si_pid is left zeroed if there's no child in a waitable state.
+    Ok(siginfo_pid(&info) != 0)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn siginfo_pid(info: &libc::siginfo_t) -> libc::pid_t {
+    unsafe { info.si_pid() }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+fn siginfo_pid(info: &libc::siginfo_t) -> libc::pid_t {
+    info.si_pid
+}
+
+/// Closes all descriptors starting from 3 except the given ones.
+fn close_all_fds_except(mut keep: [RawFd; 2]) -> nix::Result<()> {
+    keep.sort_unstable();
+
+    let mut first = 3;
+    for &fd in &keep {
+        if fd >= first {
+            close_fd_range(first, fd - 1)?;
+            first = fd + 1;
+        }
+    }
+
+    close_fd_range(first, RawFd::MAX)
+}
+
+#[cfg(target_os = "linux")]
+fn close_fd_range(first: RawFd, last: RawFd) -> nix::Result<()> {
+    if first > last {
+        return Ok(());
+    }
+
+    let ret = unsafe {
+        libc::syscall(
+            libc::SYS_close_range,
+            first as libc::c_uint,
+            last as libc::c_uint,
+            0 as libc::c_uint,
+        )
+    };
+    if ret == 0 {
+        return Ok(());
+    }
+
+    // close_range(2) is not available on kernels older than 5.9
+    close_fd_range_one_by_one(first, last)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn close_fd_range(first: RawFd, last: RawFd) -> nix::Result<()> {
+    close_fd_range_one_by_one(first, last)
+}
+
+fn close_fd_range_one_by_one(first: RawFd, last: RawFd) -> nix::Result<()> {
+    // on linux could be used getrlimit(RLIMIT_NOFILE, rlim) interface
+    let max_open_fds = sysconf(SysconfVar::OPEN_MAX)?.unwrap() as RawFd;
+    let last = std::cmp::min(last, max_open_fds - 1);
+
+    // Why closing FD 1 causes an endless loop
+    (first..=last).for_each(|fd| {
+        let _ = close(fd);
+    });
+
+    Ok(())
+}
+
+fn set_echo(fd: RawFd, on: bool) -> nix::Result<()> {
     // Set echo off
     // Even though there may be something left behind https://stackoverflow.com/a/59034084
+    set_local_flags(fd, termios::LocalFlags::ECHO, on)
+}
+
+fn set_local_flags(fd: RawFd, local_flags: termios::LocalFlags, on: bool) -> nix::Result<()> {
     let mut flags = termios::tcgetattr(fd)?;
-    match on {
-        true => flags.local_flags |= termios::LocalFlags::ECHO,
-        false => flags.local_flags &= !termios::LocalFlags::ECHO,
-    }
+    flags.local_flags.set(local_flags, on);
 
     termios::tcsetattr(fd, termios::SetArg::TCSANOW, &flags)?;
     Ok(())
 }
 
-fn set_raw(fd: RawFd) -> Result<()> {
+fn set_raw(fd: RawFd) -> nix::Result<()> {
     let mut flags = termios::tcgetattr(fd)?;
 
     #[cfg(not(target_os = "macos"))]
@@ -862,33 +4244,50 @@ fn set_raw(fd: RawFd) -> Result<()> {
     Ok(())
 }
 
-fn get_this_term_char(char: SpecialCharacterIndices) -> Option<u8> {
-    for &fd in &[STDIN_FILENO, STDOUT_FILENO] {
-        if let Ok(char) = get_term_char(fd, char) {
-            return Some(char);
-        }
-    }
-
-    None
-}
+fn set_canonical(fd: RawFd) -> nix::Result<()> {
+    use termios::{InputFlags, LocalFlags, OutputFlags};
 
-fn get_intr_char() -> u8 {
-    get_this_term_char(SpecialCharacterIndices::VINTR).unwrap_or(DEFAULT_INTR_CHAR)
-}
+    let mut flags = termios::tcgetattr(fd)?;
+    flags.local_flags |=
+        LocalFlags::ICANON | LocalFlags::ECHO | LocalFlags::ISIG | LocalFlags::IEXTEN;
+    flags.input_flags |= InputFlags::ICRNL;
+    flags.output_flags |= OutputFlags::OPOST;
 
-fn get_eof_char() -> u8 {
-    get_this_term_char(SpecialCharacterIndices::VEOF).unwrap_or(DEFAULT_VEOF_CHAR)
+    termios::tcsetattr(fd, termios::SetArg::TCSANOW, &flags)?;
+    Ok(())
 }
 
-fn get_term_char(fd: RawFd, char: SpecialCharacterIndices) -> Result<u8> {
+fn get_term_char(fd: RawFd, char: SpecialCharacterIndices) -> nix::Result<u8> {
     let flags = termios::tcgetattr(fd)?;
     let b = flags.control_chars[char as usize];
     Ok(b)
 }
 
-fn make_controlling_tty(child_name: &str) -> Result<()> {
-    // Is this appoach's result the same as just call ioctl TIOCSCTTY?
+/// Sets all signals to their default actions and unblocks them.
+fn reset_signal_handling() -> nix::Result<()> {
+    let default = signal::SigAction::new(
+        signal::SigHandler::SigDfl,
+        signal::SaFlags::empty(),
+        signal::SigSet::empty(),
+    );
+
+    for sig in signal::Signal::iterator() {
+        // their actions can't be changed
+        if sig == signal::Signal::SIGKILL || sig == signal::Signal::SIGSTOP {
+            continue;
+        }
+
+        unsafe { signal::sigaction(sig, &default)? };
+    }
+
+    signal::sigprocmask(
+        signal::SigmaskHow::SIG_SETMASK,
+        Some(&signal::SigSet::empty()),
+        None,
+    )
+}
 
+fn make_controlling_tty(child_name: &str, slave_fd: RawFd) -> nix::Result<()> {
     // Disconnect from controlling tty, if any
     let fd = open("/dev/tty", OFlag::O_RDWR | OFlag::O_NOCTTY, Mode::empty());
     match fd {
@@ -917,9 +4316,7 @@ fn make_controlling_tty(child_name: &str) -> Result<()> {
         Err(_) => return Err(Error::UnsupportedOperation),
     }
 
-    // Verify we can open child pty.
-    let fd = open(child_name, OFlag::O_RDWR, Mode::empty())?;
-    close(fd)?;
+    acquire_controlling_tty(child_name, slave_fd)?;
 
     // Verify we now have a controlling tty.
     let fd = open("/dev/tty", OFlag::O_WRONLY, Mode::empty())?;
@@ -928,12 +4325,48 @@ fn make_controlling_tty(child_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Makes a PTY slave a controlling terminal of a calling session leader.
+#[cfg(not(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd",
+    target_os = "netbsd"
+)))]
+fn acquire_controlling_tty(child_name: &str, _: RawFd) -> nix::Result<()> {
+    // A terminal opened by a session leader without a controlling terminal becomes the one.
+    let fd = open(child_name, OFlag::O_RDWR, Mode::empty())?;
+    close(fd)?;
+
+    Ok(())
+}
+
+/// Makes a PTY slave a controlling terminal of a calling session leader.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "openbsd",
+    target_os = "netbsd"
+))]
+fn acquire_controlling_tty(_: &str, slave_fd: RawFd) -> nix::Result<()> {
+    // BSDs don't assign a controlling terminal on open,
+    // it must be set explicitly.
+    nix::ioctl_write_int_bad!(_set_controlling_tty, libc::TIOCSCTTY);
+
+    let _ = unsafe { _set_controlling_tty(slave_fd, 0) }?;
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn create_pty() -> Result<()> {
+    fn create_pty() -> nix::Result<()> {
         let master = Master::open()?;
         master.grant_slave_access()?;
         master.unlock_slave()?;
@@ -945,7 +4378,7 @@ mod tests {
 
     #[test]
     #[ignore = "The test should be run in a sigle thread mode --jobs 1 or --test-threads 1"]
-    fn release_pty_master() -> Result<()> {
+    fn release_pty_master() -> nix::Result<()> {
         let master = Master::open()?;
         let old_master_fd = master.fd.as_raw_fd();
 
@@ -957,4 +4390,94 @@ mod tests {
 
         Ok(())
     }
+
+    #[cfg(feature = "sync")]
+    #[test]
+    fn interact_log() {
+        use std::{cell::RefCell, rc::Rc};
+
+        // gives a line and then the escape character once the line is echoed back
+        struct Input {
+            sent: bool,
+            output: Rc<RefCell<Vec<u8>>>,
+        }
+
+        impl Read for Input {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let data: &[u8] = if !self.sent {
+                    self.sent = true;
+                    b"hello\n"
+                } else if self.output.borrow().ends_with(b"hello\r\n") {
+                    &[0x1d]
+                } else {
+                    return Err(io::ErrorKind::WouldBlock.into());
+                };
+
+                buf[..data.len()].copy_from_slice(data);
+                Ok(data.len())
+            }
+        }
+
+        struct Output(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for Output {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.borrow_mut().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let input = Input {
+            sent: false,
+            output: output.clone(),
+        };
+
+        let mut log = Vec::new();
+        let status = proc
+            ._interact_io(input, Output(output.clone()), 0x1d, None, Some(&mut log))
+            .unwrap();
+        assert_eq!(status, WaitStatus::StillAlive);
+
+        let log = String::from_utf8(log).unwrap();
+        let lines = log.lines().collect::<Vec<_>>();
+        assert_eq!(lines[0], r#"> "hello\n""#, "{:?}", log);
+        assert!(lines[1..].iter().all(|line| line.starts_with("< \"")));
+        let echoed = output.borrow().escape_ascii().to_string();
+        let logged = lines[1..]
+            .iter()
+            .map(|line| &line[3..line.len() - 1])
+            .collect::<String>();
+        assert_eq!(logged, echoed);
+        assert!(logged.ends_with(r"hello\r\n"), "{:?}", log);
+
+        assert!(proc.exit(true).unwrap());
+    }
+
+    #[test]
+    fn env_winsize() {
+        let env = |columns: &'static str, lines: &'static str| {
+            move |var: &str| match var {
+                "COLUMNS" if !columns.is_empty() => Ok(columns.to_owned()),
+                "LINES" if !lines.is_empty() => Ok(lines.to_owned()),
+                _ => Err(std::env::VarError::NotPresent),
+            }
+        };
+
+        assert_eq!(get_env_winsize(env("120", "40")).unwrap(), (120, 40));
+        assert_eq!(get_env_winsize(env(" 120\n", "40")).unwrap(), (120, 40));
+        assert!(matches!(
+            get_env_winsize(env("120", "lines")),
+            Err(PtyError::Winsize(err)) if err.kind() == io::ErrorKind::InvalidData
+        ));
+        assert!(matches!(
+            get_env_winsize(env("120", "")),
+            Err(PtyError::Winsize(err)) if err.kind() == io::ErrorKind::NotFound
+        ));
+    }
 }
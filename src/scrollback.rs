@@ -0,0 +1,96 @@
+use crate::stream::has_reached_end_of_sdtout;
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+/// ScrollbackReader keeps reading a child's output in a background thread
+/// and keeps its last bytes in a bounded buffer.
+///
+/// A child blocks on a write once a PTY buffer is full,
+/// so if nobody reads the master the child gets stuck.
+/// The reader makes sure the output is always consumed while only the last `capacity` bytes are kept,
+/// as a scrollback of a terminal emulator does.
+///
+/// The thread stops on `EOF` or an error.
+///
+/// ```no_run
+/// use ptyprocess::{PtyProcess, ScrollbackReader};
+/// use std::process::Command;
+///
+/// let process = PtyProcess::spawn(Command::new("ls")).unwrap();
+/// let scrollback = ScrollbackReader::new(process.get_pty_handle().unwrap(), 64 * 1024);
+///
+/// process.wait().unwrap();
+/// let output = scrollback.join().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct ScrollbackReader {
+    buffer: Arc<Mutex<VecDeque<u8>>>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl ScrollbackReader {
+    /// Starts reading from a given reader (e.g [crate::PtyReader]) in a background thread,
+    /// keeping the last `capacity` bytes.
+    pub fn new<R>(mut reader: R, capacity: usize) -> Self
+    where
+        R: Read + Send + 'static,
+    {
+        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(capacity)));
+        let handle = thread::spawn({
+            let buffer = buffer.clone();
+            move || {
+                let mut buf = [0; 4096];
+                loop {
+                    let n = match reader.read(&mut buf) {
+                        Ok(0) => return Ok(()),
+                        Ok(n) => n,
+                        Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                        // a descriptor may be in a non-blocking mode (e.g it's used by an async runtime)
+                        Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                            thread::sleep(Duration::from_millis(10));
+                            continue;
+                        }
+                        Err(ref err) if has_reached_end_of_sdtout(err) => return Ok(()),
+                        Err(err) => return Err(err),
+                    };
+
+                    let mut buffer = buffer.lock().unwrap();
+                    let data = &buf[n.saturating_sub(capacity)..n];
+                    let overflow = (buffer.len() + data.len()).saturating_sub(capacity);
+                    buffer.drain(..overflow);
+                    buffer.extend(data);
+                }
+            }
+        });
+
+        Self { buffer, handle }
+    }
+
+    /// Returns a copy of the last bytes read so far.
+    pub fn snapshot(&self) -> Vec<u8> {
+        self.buffer.lock().unwrap().iter().copied().collect()
+    }
+
+    /// Checks whether the reading thread has stopped.
+    pub fn is_finished(&self) -> bool {
+        self.handle.is_finished()
+    }
+
+    /// Waits until the reading thread stops and returns the last bytes.
+    ///
+    /// It returns an error if reading has failed.
+    pub fn join(self) -> io::Result<Vec<u8>> {
+        let Self { buffer, handle } = self;
+        handle
+            .join()
+            .map_err(|_| io::Error::new(io::ErrorKind::Other, "a scrollback thread panicked"))??;
+
+        let buffer = buffer.lock().unwrap();
+        Ok(buffer.iter().copied().collect())
+    }
+}
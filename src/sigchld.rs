@@ -0,0 +1,230 @@
+use crate::error::{PtyError, Result};
+use crate::process::PtyProcess;
+use nix::fcntl::{fcntl, FcntlArg, FdFlag, OFlag};
+use nix::libc;
+use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+use nix::sys::wait::{waitpid, WaitPidFlag, WaitStatus};
+use nix::unistd::{close, pipe, read, Pid};
+use std::io;
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{Arc, Mutex};
+
+// A write end of a self-pipe the handler notifies through, -1 if it's not installed.
+static NOTIFY_FD: AtomicI32 = AtomicI32::new(-1);
+
+/// SigchldReaper reaps watched children once they exit, driven by a `SIGCHLD` handler.
+///
+/// Polling [PtyProcess::is_alive] of each child is wasteful when there are many of them.
+/// Instead a `SIGCHLD` handler writes to a self-pipe,
+/// which may be registered in an event loop by its descriptor ([AsRawFd]).
+/// Once it's readable [SigchldReaper::poll_exited] reaps the children which have exited.
+///
+/// The handler does nothing but a write to the pipe, so it's async-signal-safe.
+/// Only children which are watched are reaped,
+/// so statuses of other children (e.g spawned by [std::process::Command]) are not stolen.
+/// A status of a reaped child is still returned by [PtyProcess::wait] and others.
+///
+/// Only one reaper may exist at a time.
+/// A previous `SIGCHLD` disposition is restored once it's dropped.
+///
+/// ```no_run
+/// use ptyprocess::{PtyProcess, SigchldReaper};
+/// use std::process::Command;
+///
+/// let mut reaper = SigchldReaper::install().unwrap();
+///
+/// let process = PtyProcess::spawn(Command::new("true")).unwrap();
+/// reaper.watch(&process);
+///
+/// // wait until the reaper's descriptor is readable (e.g by poll(2))
+/// for (pid, status) in reaper.poll_exited() {
+///     println!("{} exited with {:?}", pid, status);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SigchldReaper {
+    read_fd: RawFd,
+    write_fd: RawFd,
+    previous: SigAction,
+    children: Vec<(Pid, Arc<Mutex<Option<WaitStatus>>>)>,
+    // whether children must be checked even if no signal was received
+    check: bool,
+}
+
+impl SigchldReaper {
+    /// Installs a `SIGCHLD` handler.
+    ///
+    /// It returns [PtyError::Io] error with [io::ErrorKind::AlreadyExists] kind
+    /// if another reaper is installed.
+    pub fn install() -> Result<Self> {
+        let (read_fd, write_fd) = pipe()?;
+        let close_pipe = || {
+            let _ = close(read_fd);
+            let _ = close(write_fd);
+        };
+
+        for fd in [read_fd, write_fd] {
+            let setup = fcntl(fd, FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+                .and_then(|_| fcntl(fd, FcntlArg::F_SETFL(OFlag::O_NONBLOCK)));
+            if let Err(err) = setup {
+                close_pipe();
+                return Err(PtyError::from(err));
+            }
+        }
+
+        if NOTIFY_FD
+            .compare_exchange(-1, write_fd, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            close_pipe();
+            return Err(PtyError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                "a SIGCHLD reaper is already installed",
+            )));
+        }
+
+        let action = SigAction::new(
+            SigHandler::Handler(handle_sigchld),
+            SaFlags::SA_RESTART | SaFlags::SA_NOCLDSTOP,
+            SigSet::empty(),
+        );
+        let previous = match unsafe { sigaction(Signal::SIGCHLD, &action) } {
+            Ok(previous) => previous,
+            Err(err) => {
+                NOTIFY_FD.store(-1, Ordering::SeqCst);
+                close_pipe();
+                return Err(PtyError::from(err));
+            }
+        };
+
+        Ok(Self {
+            read_fd,
+            write_fd,
+            previous,
+            children: Vec::new(),
+            check: false,
+        })
+    }
+
+    /// Starts watching a child of a [PtyProcess].
+    pub fn watch(&mut self, process: &PtyProcess) {
+        self.children
+            .push((process.pid(), process.shared_exit_status()));
+        // the child might have exited before it's watched
+        self.check = true;
+    }
+
+    /// Returns an amount of children which are watched and haven't been reaped yet.
+    pub fn watched(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Reaps watched children which have exited since the last call, without blocking.
+    ///
+    /// A reaped child is not watched anymore.
+    /// A child reaped by its [PtyProcess] in the meantime is returned as well.
+    ///
+    /// A child which can't be waited for (`waitpid` fails) stays watched
+    /// and is checked again by the next call even if no signal is received.
+    pub fn poll_exited(&mut self) -> Vec<(Pid, WaitStatus)> {
+        if !self.drain_notifications() && !std::mem::take(&mut self.check) {
+            return Vec::new();
+        }
+
+        let mut exited = Vec::new();
+        let mut retry = false;
+        self.children.retain(|(pid, exit_status)| {
+            if let Some(status) = *exit_status.lock().unwrap() {
+                exited.push((*pid, status));
+                return false;
+            }
+
+            match waitpid(*pid, Some(WaitPidFlag::WNOHANG)) {
+                Ok(status @ WaitStatus::Exited(..)) | Ok(status @ WaitStatus::Signaled(..)) => {
+                    *exit_status.lock().unwrap() = Some(status);
+                    exited.push((*pid, status));
+                    false
+                }
+                Ok(_) => true,
+                // the child might be reaped by its handle concurrently
+                Err(_) => match *exit_status.lock().unwrap() {
+                    Some(status) => {
+                        exited.push((*pid, status));
+                        false
+                    }
+                    None => {
+                        retry = true;
+                        true
+                    }
+                },
+            }
+        });
+        self.check = retry;
+
+        exited
+    }
+
+    // Reads all pending notifications and returns whether there was any.
+    fn drain_notifications(&self) -> bool {
+        let mut notified = false;
+        let mut buf = [0; 64];
+        while let Ok(n) = read(self.read_fd, &mut buf) {
+            if n == 0 {
+                break;
+            }
+
+            notified = true;
+        }
+
+        notified
+    }
+}
+
+impl AsRawFd for SigchldReaper {
+    /// Returns a descriptor which becomes readable once a `SIGCHLD` is received.
+    fn as_raw_fd(&self) -> RawFd {
+        self.read_fd
+    }
+}
+
+impl Drop for SigchldReaper {
+    fn drop(&mut self) {
+        let _ = unsafe { sigaction(Signal::SIGCHLD, &self.previous) };
+        NOTIFY_FD.store(-1, Ordering::SeqCst);
+        let _ = close(self.read_fd);
+        let _ = close(self.write_fd);
+    }
+}
+
+extern "C" fn handle_sigchld(_: libc::c_int) {
+    let fd = NOTIFY_FD.load(Ordering::SeqCst);
+    if fd < 0 {
+        return;
+    }
+
+    // write(2) may change errno which the interrupted code might rely on
+    let errno = unsafe { *errno_location() };
+    let _ = unsafe { libc::write(fd, b"\0".as_ptr().cast(), 1) };
+    unsafe { *errno_location() = errno };
+}
+
+#[cfg(any(target_os = "linux", target_os = "emscripten"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__errno_location()
+}
+
+#[cfg(any(target_os = "android", target_os = "netbsd", target_os = "openbsd"))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__errno()
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly"
+))]
+unsafe fn errno_location() -> *mut libc::c_int {
+    libc::__error()
+}
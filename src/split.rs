@@ -0,0 +1,149 @@
+use crate::stream::Stream;
+use std::fs::File;
+
+/// PtyReader is a reading half of a PTY created by [crate::PtyProcess::split] or [crate::PtyProcess::take_stdout].
+///
+/// It owns its own DUPed descriptor of a PTY master,
+/// so it can be used independently of a [crate::PtyProcess] and a [PtyWriter].
+#[derive(Debug)]
+pub struct PtyReader {
+    stream: Stream,
+}
+
+/// PtyWriter is a writing half of a PTY created by [crate::PtyProcess::split] or [crate::PtyProcess::take_stdin].
+///
+/// It owns its own DUPed descriptor of a PTY master,
+/// so it can be used independently of a [crate::PtyProcess] and a [PtyReader].
+#[derive(Debug)]
+pub struct PtyWriter {
+    stream: Stream,
+}
+
+impl PtyReader {
+    pub(crate) fn new(file: File) -> Self {
+        Self::from_stream(Stream::new(file))
+    }
+
+    pub(crate) fn from_stream(stream: Stream) -> Self {
+        Self { stream }
+    }
+}
+
+impl PtyWriter {
+    pub(crate) fn new(file: File) -> Self {
+        Self {
+            stream: Stream::new(file),
+        }
+    }
+}
+
+#[cfg(feature = "sync")]
+mod sync_split {
+    use super::{PtyReader, PtyWriter};
+    use std::io::{self, BufRead, Read, Write};
+
+    impl PtyReader {
+        /// Try to read in a non-blocking mode.
+        ///
+        /// It returns `Ok(None)` if there's nothing to read.
+        pub fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+            self.stream.try_read(buf)
+        }
+    }
+
+    impl Read for PtyReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.stream.read(buf)
+        }
+    }
+
+    impl BufRead for PtyReader {
+        fn fill_buf(&mut self) -> io::Result<&[u8]> {
+            self.stream.fill_buf()
+        }
+
+        fn consume(&mut self, amt: usize) {
+            self.stream.consume(amt)
+        }
+    }
+
+    impl Write for PtyWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.stream.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.stream.flush()
+        }
+
+        fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+            self.stream.write_vectored(bufs)
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+mod async_split {
+    use super::{PtyReader, PtyWriter};
+    use futures_lite::{AsyncBufRead, AsyncRead, AsyncWrite};
+    use std::{
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    impl PtyReader {
+        /// Try to read in a non-blocking mode.
+        ///
+        /// It returns `Ok(None)` if there's nothing to read.
+        pub async fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
+            self.stream.try_read(buf).await
+        }
+    }
+
+    impl AsyncRead for PtyReader {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.stream).poll_read(cx, buf)
+        }
+    }
+
+    impl AsyncBufRead for PtyReader {
+        fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+            Pin::new(&mut self.get_mut().stream).poll_fill_buf(cx)
+        }
+
+        fn consume(mut self: Pin<&mut Self>, amt: usize) {
+            Pin::new(&mut self.stream).consume(amt)
+        }
+    }
+
+    impl AsyncWrite for PtyWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.stream).poll_write(cx, buf)
+        }
+
+        fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.stream).poll_flush(cx)
+        }
+
+        fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Pin::new(&mut self.stream).poll_close(cx)
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            bufs: &[io::IoSlice<'_>],
+        ) -> Poll<io::Result<usize>> {
+            Pin::new(&mut self.stream).poll_write_vectored(cx, bufs)
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use nix::sys::signal::Signal;
+use nix::sys::wait::WaitStatus;
+use std::fmt;
+
+/// PtyExitStatus describes a status of a child process.
+///
+/// It's a wrapper around [WaitStatus] with accessors similar to [std::process::ExitStatus],
+/// so a caller doesn't have to match on it.
+///
+/// ```
+/// use ptyprocess::PtyProcess;
+/// use std::process::Command;
+///
+/// let mut command = Command::new("sh");
+/// command.args(["-c", "exit 1"]);
+/// let process = PtyProcess::spawn(command).unwrap();
+///
+/// let status = process.wait_status().unwrap();
+/// assert!(!status.success());
+/// assert_eq!(status.code(), Some(1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PtyExitStatus(WaitStatus);
+
+impl PtyExitStatus {
+    /// Returns true if a process exited with code 0.
+    pub fn success(&self) -> bool {
+        self.code() == Some(0)
+    }
+
+    /// Returns an exit code if a process exited normally.
+    pub fn code(&self) -> Option<i32> {
+        match self.0 {
+            WaitStatus::Exited(_, code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns a signal which killed a process.
+    pub fn signal(&self) -> Option<Signal> {
+        match self.0 {
+            WaitStatus::Signaled(_, signal, _) => Some(signal),
+            _ => None,
+        }
+    }
+
+    /// Returns true if a process was killed by a signal and produced a core dump.
+    pub fn core_dumped(&self) -> bool {
+        matches!(self.0, WaitStatus::Signaled(_, _, true))
+    }
+
+    /// Returns a signal which stopped a process.
+    pub fn stopped_signal(&self) -> Option<Signal> {
+        match self.0 {
+            WaitStatus::Stopped(_, signal) => Some(signal),
+            _ => None,
+        }
+    }
+
+    /// Returns an underlying status.
+    pub fn wait_status(&self) -> WaitStatus {
+        self.0
+    }
+}
+
+impl From<WaitStatus> for PtyExitStatus {
+    fn from(status: WaitStatus) -> Self {
+        Self(status)
+    }
+}
+
+impl fmt::Display for PtyExitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&describe_status(self.0))
+    }
+}
+
+/// Returns a human-readable description of a process status.
+///
+/// ```
+/// use ptyprocess::{describe_status, PtyProcess};
+/// use std::process::Command;
+///
+/// let mut command = Command::new("sh");
+/// command.args(["-c", "exit 1"]);
+/// let process = PtyProcess::spawn(command).unwrap();
+///
+/// let status = process.wait().unwrap();
+/// assert_eq!(describe_status(status), "exited with code 1");
+/// ```
+pub fn describe_status(status: WaitStatus) -> String {
+    match status {
+        WaitStatus::Exited(_, code) => format!("exited with code {}", code),
+        WaitStatus::Signaled(_, signal, true) => {
+            format!("killed by {} (core dumped)", signal.as_str())
+        }
+        WaitStatus::Signaled(_, signal, false) => format!("killed by {}", signal.as_str()),
+        WaitStatus::Stopped(_, signal) => format!("stopped by {}", signal.as_str()),
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        WaitStatus::PtraceEvent(_, signal, event) => {
+            format!("stopped by {} (ptrace event {})", signal.as_str(), event)
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        WaitStatus::PtraceSyscall(_) => "stopped at a system call".to_string(),
+        WaitStatus::Continued(_) => "continued".to_string(),
+        WaitStatus::StillAlive => "still running".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nix::unistd::Pid;
+
+    #[test]
+    fn describe() {
+        let pid = Pid::from_raw(1);
+
+        assert_eq!(
+            describe_status(WaitStatus::Exited(pid, 1)),
+            "exited with code 1"
+        );
+        assert_eq!(
+            describe_status(WaitStatus::Signaled(pid, Signal::SIGSEGV, true)),
+            "killed by SIGSEGV (core dumped)"
+        );
+        assert_eq!(
+            describe_status(WaitStatus::Signaled(pid, Signal::SIGKILL, false)),
+            "killed by SIGKILL"
+        );
+        assert_eq!(
+            describe_status(WaitStatus::Stopped(pid, Signal::SIGTSTP)),
+            "stopped by SIGTSTP"
+        );
+        assert_eq!(describe_status(WaitStatus::Continued(pid)), "continued");
+        assert_eq!(describe_status(WaitStatus::StillAlive), "still running");
+    }
+
+    #[test]
+    fn exit_status() {
+        let pid = Pid::from_raw(1);
+
+        let status = PtyExitStatus::from(WaitStatus::Exited(pid, 0));
+        assert!(status.success());
+        assert_eq!(status.code(), Some(0));
+        assert_eq!(status.signal(), None);
+
+        let status = PtyExitStatus::from(WaitStatus::Exited(pid, 2));
+        assert!(!status.success());
+        assert_eq!(status.code(), Some(2));
+
+        let status = PtyExitStatus::from(WaitStatus::Signaled(pid, Signal::SIGSEGV, true));
+        assert!(!status.success());
+        assert_eq!(status.code(), None);
+        assert_eq!(status.signal(), Some(Signal::SIGSEGV));
+        assert!(status.core_dumped());
+        assert_eq!(status.to_string(), "killed by SIGSEGV (core dumped)");
+
+        let status = PtyExitStatus::from(WaitStatus::Stopped(pid, Signal::SIGTSTP));
+        assert_eq!(status.signal(), None);
+        assert!(!status.core_dumped());
+        assert_eq!(status.stopped_signal(), Some(Signal::SIGTSTP));
+        assert_eq!(
+            status.wait_status(),
+            WaitStatus::Stopped(pid, Signal::SIGTSTP)
+        );
+    }
+}
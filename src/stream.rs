@@ -1,21 +1,36 @@
 /// Stream represent a IO stream.
+///
+/// Reads are buffered, so reading by small portions (e.g byte by byte)
+/// doesn't issue a system call for each read.
+/// Non-blocking reads return buffered data first.
 #[cfg(feature = "sync")]
 pub type Stream = sync_stream::Stream;
 /// Stream represent a IO stream.
+///
+/// Reads are buffered, so reading by small portions (e.g byte by byte)
+/// doesn't issue a system call for each read.
+/// Non-blocking reads return buffered data first.
 #[cfg(feature = "async")]
 pub type Stream = async_stream::AsyncStream;
 
+/// A default capacity of a read buffer.
+pub(crate) const DEFAULT_CAPACITY: usize = 8 * 1024;
+
 #[cfg(feature = "sync")]
 mod sync_stream {
     use super::has_reached_end_of_sdtout;
+    use crate::error::nix_error_to_io;
     use nix::{
+        errno::Errno,
         fcntl::{fcntl, FcntlArg, OFlag},
-        Result,
+        poll::{poll, PollFd, PollFlags},
+        Error, Result,
     };
     use std::{
         fs::File,
         io::{self, BufRead, BufReader, Read, Write},
         os::unix::prelude::{AsRawFd, RawFd},
+        time::Duration,
     };
 
     /// Stream represent a IO stream.
@@ -28,15 +43,27 @@ mod sync_stream {
     #[derive(Debug)]
     struct Reader {
         inner: File,
+        timeout: Option<Duration>,
     }
 
     impl Stream {
         /// The function returns a new Stream from a file.
         pub fn new(file: File) -> Self {
+            Self::with_capacity(super::DEFAULT_CAPACITY, file)
+        }
+
+        /// The function returns a new Stream from a file with a given read buffer capacity.
+        pub fn with_capacity(capacity: usize, file: File) -> Self {
             let copy_file = file
                 .try_clone()
                 .expect("It's ok to clone fd as it will be just DUPed");
-            let reader = BufReader::new(Reader { inner: copy_file });
+            let reader = BufReader::with_capacity(
+                capacity,
+                Reader {
+                    inner: copy_file,
+                    timeout: None,
+                },
+            );
 
             Self {
                 inner: file,
@@ -44,27 +71,73 @@ mod sync_stream {
             }
         }
 
+        /// Returns true if there's data in an internal buffer.
+        pub fn has_buffered_data(&self) -> bool {
+            self.buffered_len() > 0
+        }
+
+        /// Returns an amount of bytes in an internal buffer.
+        pub fn buffered_len(&self) -> usize {
+            self.reader.buffer().len()
+        }
+
         /// Try to read in a non-blocking mode.
         ///
         /// It returns:
         ///     - Ok(None) if there's nothing to read.
         ///     - Ok(Some(n)) an amount of bytes were read.
         ///     - Err(err) an IO error which occured.
-        pub fn try_read(&mut self, mut buf: &mut [u8]) -> io::Result<Option<usize>> {
+        pub fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
             let fd = self.inner.as_raw_fd();
-            make_non_blocking(fd).map_err(nix_error_to_io)?;
+            with_non_blocking(fd, || self.read(buf))
+        }
 
-            let result = match self.read(&mut buf) {
-                Ok(n) => Ok(Some(n)),
-                Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
-                Err(err) => Err(err),
-            };
+        /// Try to fill an internal buffer in a non-blocking mode.
+        ///
+        /// Unlike [`try_read`] it doesn't consume data,
+        /// use [BufRead::consume] to do it.
+        ///
+        /// It returns:
+        ///     - Ok(None) if there's nothing to read.
+        ///     - Ok(Some(buf)) a buffered data, empty on eof.
+        ///     - Err(err) an IO error which occured.
+        ///
+        /// [`try_read`]: #method.try_read
+        pub fn try_fill_buf(&mut self) -> io::Result<Option<&[u8]>> {
+            let fd = self.inner.as_raw_fd();
+            let reader = &mut self.reader;
+            with_non_blocking(fd, move || reader.fill_buf())
+        }
 
-            // As file is DUPed changes in one descriptor affects all ones
-            // so we need to make blocking file after we finished.
-            make_blocking(fd).map_err(nix_error_to_io)?;
+        /// Try to write in a non-blocking mode.
+        ///
+        /// It returns:
+        ///     - Ok(None) if nothing can be written now (e.g a child's input buffer is full).
+        ///     - Ok(Some(n)) an amount of bytes were written, which may be less than `buf.len()`.
+        ///     - Err(err) an IO error which occured.
+        pub fn try_write(&mut self, buf: &[u8]) -> io::Result<Option<usize>> {
+            let fd = self.inner.as_raw_fd();
+            with_non_blocking(fd, || self.inner.write(buf))
+        }
 
-            result
+        /// Sets or clears `O_NONBLOCK` on the descriptor.
+        ///
+        /// In a non-blocking mode [Read] and [Write] operations return
+        /// an error with [io::ErrorKind::WouldBlock] kind instead of blocking.
+        /// `try_*` operations behave the same in both modes.
+        pub fn set_nonblocking(&mut self, on: bool) -> io::Result<()> {
+            set_non_blocking(self.inner.as_raw_fd(), on)
+                .map(|_| ())
+                .map_err(nix_error_to_io)
+        }
+
+        /// Sets a timeout of blocking reads.
+        ///
+        /// A read which doesn't get any data within the timeout
+        /// returns an error with [io::ErrorKind::TimedOut] kind.
+        /// `None` turns the timeout off.
+        pub fn set_read_timeout(&mut self, timeout: Option<Duration>) {
+            self.reader.get_mut().timeout = timeout;
         }
 
         /// Try to read a byte in a non-blocking mode.
@@ -102,8 +175,37 @@ mod sync_stream {
         }
     }
 
+    impl Reader {
+        /// Waits until the descriptor is readable or a timeout is reached.
+        ///
+        /// It doesn't wait in a non-blocking mode.
+        fn wait_readable(&self, timeout: Duration) -> io::Result<()> {
+            let fd = self.inner.as_raw_fd();
+            let flags = fcntl(fd, FcntlArg::F_GETFL).map_err(nix_error_to_io)?;
+            if OFlag::from_bits_truncate(flags).contains(OFlag::O_NONBLOCK) {
+                return Ok(());
+            }
+
+            let timeout_ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+            let mut fds = [PollFd::new(fd, PollFlags::POLLIN)];
+            match poll(&mut fds, timeout_ms) {
+                Ok(0) => Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "no data was read within a timeout",
+                )),
+                Ok(_) => Ok(()),
+                Err(Error::Sys(Errno::EINTR)) => Err(io::ErrorKind::Interrupted.into()),
+                Err(err) => Err(nix_error_to_io(err)),
+            }
+        }
+    }
+
     impl Read for Reader {
         fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if let Some(timeout) = self.timeout {
+                self.wait_readable(timeout)?;
+            }
+
             match self.inner.read(buf) {
                 Err(ref err) if has_reached_end_of_sdtout(err) => Ok(0),
                 result => result,
@@ -127,30 +229,51 @@ mod sync_stream {
         }
     }
 
-    fn make_non_blocking(fd: RawFd) -> Result<()> {
-        _make_non_blocking(fd, true)
+    /// Runs an operation while a descriptor is in a non-blocking mode.
+    ///
+    /// A [io::ErrorKind::WouldBlock] error of the operation is returned as `Ok(None)`.
+    fn with_non_blocking<T, F>(fd: RawFd, f: F) -> io::Result<Option<T>>
+    where
+        F: FnOnce() -> io::Result<T>,
+    {
+        let changed = make_non_blocking(fd).map_err(nix_error_to_io)?;
+
+        let result = match f() {
+            Ok(value) => Ok(Some(value)),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(err) => Err(err),
+        };
+
+        // As file is DUPed changes in one descriptor affects all ones
+        // so we need to make blocking file after we finished,
+        // unless it was made non-blocking by a user.
+        if changed {
+            make_blocking(fd).map_err(nix_error_to_io)?;
+        }
+
+        result
+    }
+
+    /// Makes a descriptor non-blocking and returns whether it was blocking.
+    fn make_non_blocking(fd: RawFd) -> Result<bool> {
+        set_non_blocking(fd, true)
     }
 
     fn make_blocking(fd: RawFd) -> Result<()> {
-        _make_non_blocking(fd, false)
+        set_non_blocking(fd, false).map(|_| ())
     }
 
-    fn _make_non_blocking(fd: RawFd, blocking: bool) -> Result<()> {
+    /// Sets `O_NONBLOCK` and returns whether it was changed.
+    fn set_non_blocking(fd: RawFd, on: bool) -> Result<bool> {
         let opt = fcntl(fd, FcntlArg::F_GETFL)?;
         let mut opt = OFlag::from_bits_truncate(opt);
-        opt.set(OFlag::O_NONBLOCK, blocking);
-        fcntl(fd, FcntlArg::F_SETFL(opt))?;
-        Ok(())
-    }
-
-    fn nix_error_to_io(err: nix::Error) -> io::Error {
-        match err.as_errno() {
-            Some(code) => io::Error::from_raw_os_error(code as _),
-            None => io::Error::new(
-                io::ErrorKind::Other,
-                "Unexpected error type conversion from nix to io",
-            ),
+        if opt.contains(OFlag::O_NONBLOCK) == on {
+            return Ok(false);
         }
+
+        opt.set(OFlag::O_NONBLOCK, on);
+        fcntl(fd, FcntlArg::F_SETFL(opt))?;
+        Ok(true)
     }
 }
 
@@ -161,7 +284,7 @@ mod async_stream {
     use futures_lite::{io::BufReader, AsyncBufRead, AsyncRead, AsyncWrite};
     use std::{
         fs::File,
-        io::{self, Read},
+        io::{self, Read, Write},
         pin::Pin,
         task::{Context, Poll},
     };
@@ -182,11 +305,19 @@ mod async_stream {
     impl AsyncStream {
         /// The function returns a new Stream from a file.
         pub fn new(file: File) -> Self {
+            Self::with_capacity(DEFAULT_CAPACITY, file)
+        }
+
+        /// The function returns a new Stream from a file with a given read buffer capacity.
+        pub fn with_capacity(capacity: usize, file: File) -> Self {
             let cloned = file.try_clone().unwrap();
             let file = Async::new(file).unwrap();
-            let reader = BufReader::new(Reader {
-                inner: Async::new(cloned).unwrap(),
-            });
+            let reader = BufReader::with_capacity(
+                capacity,
+                Reader {
+                    inner: Async::new(cloned).unwrap(),
+                },
+            );
 
             Self {
                 inner: file,
@@ -194,13 +325,23 @@ mod async_stream {
             }
         }
 
+        /// Returns true if there's data in an internal buffer.
+        pub fn has_buffered_data(&self) -> bool {
+            self.buffered_len() > 0
+        }
+
+        /// Returns an amount of bytes in an internal buffer.
+        pub fn buffered_len(&self) -> usize {
+            self.reader.buffer().len()
+        }
+
         /// Try to read in a non-blocking mode.
         ///
         /// It returns:
         ///     - Ok(None) if there's nothing to read.
         ///     - Ok(Some(n)) an amount of bytes were read.
         ///     - Err(err) an IO error which occured.
-        pub async fn try_read(&mut self, mut buf: &mut [u8]) -> io::Result<Option<usize>> {
+        pub async fn try_read(&mut self, buf: &mut [u8]) -> io::Result<Option<usize>> {
             // future::poll_once was testing but it doesn't work why?
             // let a = future::poll_once(self.reader.read(buf)).await;
             // match a {
@@ -212,8 +353,18 @@ mod async_stream {
             //     None => Ok(None),
             // }
 
+            // Data which was already buffered must be returned first,
+            // otherwise it would be hidden by a following read from the fd.
+            let buffered = self.reader.buffer();
+            if !buffered.is_empty() {
+                let n = std::cmp::min(buffered.len(), buf.len());
+                buf[..n].copy_from_slice(&buffered[..n]);
+                Pin::new(&mut self.reader).consume(n);
+                return Ok(Some(n));
+            }
+
             // A fd already in a non-blocking mode
-            match self.reader.get_mut().inner.as_mut().read(&mut buf) {
+            match self.reader.get_mut().inner.as_mut().read(buf) {
                 Ok(n) => Ok(Some(n)),
                 Err(ref err) if has_reached_end_of_sdtout(err) => Ok(Some(0)),
                 Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
@@ -221,6 +372,21 @@ mod async_stream {
             }
         }
 
+        /// Try to write in a non-blocking mode.
+        ///
+        /// It returns:
+        ///     - Ok(None) if nothing can be written now (e.g a child's input buffer is full).
+        ///     - Ok(Some(n)) an amount of bytes were written, which may be less than `buf.len()`.
+        ///     - Err(err) an IO error which occured.
+        pub async fn try_write(&mut self, buf: &[u8]) -> io::Result<Option<usize>> {
+            // A fd already in a non-blocking mode
+            match self.inner.as_mut().write(buf) {
+                Ok(n) => Ok(Some(n)),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(None),
+                Err(err) => Err(err),
+            }
+        }
+
         /// Try to read a byte in a non-blocking mode.
         ///
         /// Returns:
@@ -309,9 +475,98 @@ mod async_stream {
     }
 }
 
+#[cfg(feature = "tokio")]
+pub use tokio_stream::TokioStream;
+
+#[cfg(feature = "tokio")]
+mod tokio_stream {
+    use super::has_reached_end_of_sdtout;
+    use crate::error::nix_error_to_io;
+    use nix::fcntl::{fcntl, FcntlArg, OFlag};
+    use std::{
+        fs::File,
+        io::{self, Read, Write},
+        os::unix::prelude::AsRawFd,
+        pin::Pin,
+        task::{ready, Context, Poll},
+    };
+    use tokio::io::{unix::AsyncFd, AsyncRead, AsyncWrite, ReadBuf};
+
+    /// TokioStream represent a IO stream registered in a tokio reactor.
+    #[derive(Debug)]
+    pub struct TokioStream {
+        inner: AsyncFd<File>,
+    }
+
+    impl TokioStream {
+        /// The function returns a new TokioStream from a file.
+        ///
+        /// The file is put into a non-blocking mode.
+        /// It must be called within a tokio runtime.
+        pub fn new(file: File) -> io::Result<Self> {
+            let fd = file.as_raw_fd();
+            let opt = fcntl(fd, FcntlArg::F_GETFL).map_err(nix_error_to_io)?;
+            let opt = OFlag::from_bits_truncate(opt) | OFlag::O_NONBLOCK;
+            fcntl(fd, FcntlArg::F_SETFL(opt)).map_err(nix_error_to_io)?;
+
+            let inner = AsyncFd::new(file)?;
+
+            Ok(Self { inner })
+        }
+    }
+
+    impl AsyncRead for TokioStream {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            loop {
+                let mut guard = ready!(self.inner.poll_read_ready(cx))?;
+                let unfilled = buf.initialize_unfilled();
+                match guard.try_io(|inner| inner.get_ref().read(unfilled)) {
+                    Ok(Ok(n)) => {
+                        buf.advance(n);
+                        return Poll::Ready(Ok(()));
+                    }
+                    Ok(Err(ref err)) if has_reached_end_of_sdtout(err) => {
+                        return Poll::Ready(Ok(()))
+                    }
+                    Ok(Err(err)) => return Poll::Ready(Err(err)),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+    }
+
+    impl AsyncWrite for TokioStream {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            loop {
+                let mut guard = ready!(self.inner.poll_write_ready(cx))?;
+                match guard.try_io(|inner| inner.get_ref().write(buf)) {
+                    Ok(result) => return Poll::Ready(result),
+                    Err(_would_block) => continue,
+                }
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+}
+
 /// PTY may doesn't have anything to read but the process is not DEAD,
 /// and this erorr may be returned.  
-fn has_reached_end_of_sdtout(err: &std::io::Error) -> bool {
+pub(crate) fn has_reached_end_of_sdtout(err: &std::io::Error) -> bool {
     // We don't match `err.kind()` because on stable we would expect `Other` but for those who uses nightly
     // we would need to expect `Uncategorized` behind `#![feature(io_error_uncategorized)]` unstable feature.
     // https://doc.rust-lang.org/beta/unstable-book/library-features/io-error-uncategorized.html
@@ -0,0 +1,72 @@
+use nix::libc::{self, c_char};
+use nix::unistd::Pid;
+use std::{io, time::SystemTime};
+
+/// UtmpEntry is a utmp record of a PTY session, which can be removed later.
+#[derive(Debug)]
+pub(crate) struct UtmpEntry {
+    pid: Pid,
+    line: String,
+}
+
+impl UtmpEntry {
+    /// Writes a record of a user's session on a PTY slave (e.g. `/dev/pts/3`) run by a given process.
+    pub(crate) fn register(pid: Pid, slave_name: &str, user: &str) -> io::Result<Self> {
+        let line = slave_name.trim_start_matches("/dev/").to_owned();
+        let entry = Self { pid, line };
+
+        let mut record = entry.record(libc::USER_PROCESS);
+        copy_str(&mut record.ut_user, user.as_bytes());
+        write_record(&record)?;
+
+        Ok(entry)
+    }
+
+    /// Marks a session as finished.
+    pub(crate) fn unregister(&self) -> io::Result<()> {
+        write_record(&self.record(libc::DEAD_PROCESS))
+    }
+
+    fn record(&self, kind: libc::c_short) -> libc::utmpx {
+        let mut record: libc::utmpx = unsafe { std::mem::zeroed() };
+        record.ut_type = kind;
+        record.ut_pid = self.pid.as_raw();
+        copy_str(&mut record.ut_line, self.line.as_bytes());
+
+        // An id is conventionally made of the last characters of a line (e.g. `ts/3` for `pts/3`).
+        let id_start = self.line.len().saturating_sub(record.ut_id.len());
+        copy_str(&mut record.ut_id, &self.line.as_bytes()[id_start..]);
+
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        record.ut_tv.tv_sec = now.as_secs() as _;
+        record.ut_tv.tv_usec = now.subsec_micros() as _;
+
+        record
+    }
+}
+
+fn write_record(record: &libc::utmpx) -> io::Result<()> {
+    unsafe {
+        libc::setutxent();
+        let result = libc::pututxline(record);
+        // capture an error before it's overwritten
+        let err = io::Error::last_os_error();
+        libc::endutxent();
+
+        match result.is_null() {
+            true => Err(err),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Copies a string to a fixed size field truncating it if necessary.
+///
+/// A field is not necessarily NUL terminated.
+fn copy_str(dst: &mut [c_char], src: &[u8]) {
+    for (d, &s) in dst.iter_mut().zip(src) {
+        *d = s as c_char;
+    }
+}
@@ -1,12 +1,314 @@
-use ptyprocess::PtyProcess;
+use ptyprocess::{PtyError, PtyProcess};
 use std::{io, process::Command};
 
 #[test]
 fn empty() {
     let err = PtyProcess::spawn(Command::new("")).unwrap_err();
-    let os_err = err.as_errno().unwrap() as i32;
+    assert!(matches!(err, PtyError::CommandNotFound));
+    let os_err = err.raw_os_error().unwrap();
     assert_eq!(
         io::ErrorKind::NotFound,
         io::Error::from_raw_os_error(os_err).kind()
     );
 }
+
+#[test]
+fn not_executable() {
+    let err = PtyProcess::spawn(Command::new("/dev/null")).unwrap_err();
+    match err {
+        PtyError::Exec(err) => assert_eq!(err.kind(), io::ErrorKind::PermissionDenied),
+        err => panic!("unexpected error {:?}", err),
+    }
+}
+
+#[test]
+fn pre_exec_error() {
+    let err = unsafe {
+        PtyProcess::spawn_with_pre_exec(Command::new("cat"), || {
+            Err(io::Error::from_raw_os_error(13))
+        })
+    }
+    .unwrap_err();
+    assert!(matches!(err, PtyError::Exec(_)));
+    let os_err = err.raw_os_error().unwrap();
+    assert_eq!(
+        io::ErrorKind::PermissionDenied,
+        io::Error::from_raw_os_error(os_err).kind()
+    );
+}
+
+#[test]
+fn close_fds() {
+    use ptyprocess::{PtyProcessBuilder, WaitStatus};
+    use std::os::unix::io::AsRawFd;
+
+    // a slave is opened without FD_CLOEXEC so it's inherited
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let inherited = proc.open_slave().unwrap();
+
+    let fd_exists = |close_fds| {
+        let mut command = Command::new("sh");
+        command.args(["-c", &format!("test -e /dev/fd/{}", inherited.as_raw_fd())]);
+        let proc = PtyProcessBuilder::new()
+            .command(command)
+            .close_fds(close_fds)
+            .build()
+            .unwrap();
+
+        proc.wait().unwrap() == WaitStatus::Exited(proc.pid(), 0)
+    };
+
+    assert!(!fd_exists(true));
+    assert!(fd_exists(false));
+}
+
+#[test]
+fn master_is_not_inherited() {
+    use ptyprocess::{PtyProcessBuilder, WaitStatus};
+    use std::os::unix::io::AsRawFd;
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let handle = proc.get_pty_handle().unwrap();
+
+    for fd in [proc.as_raw_fd(), handle.as_raw_fd()] {
+        let mut command = Command::new("sh");
+        command.args(["-c", &format!("test -e /dev/fd/{}", fd)]);
+        let child = PtyProcessBuilder::new()
+            .command(command)
+            .close_fds(false)
+            .build()
+            .unwrap();
+
+        assert_eq!(child.wait().unwrap(), WaitStatus::Exited(child.pid(), 1));
+    }
+}
+
+#[test]
+fn spawn_no_ctty() {
+    use ptyprocess::WaitStatus;
+
+    let has_ctty = |proc: PtyProcess| match proc.wait().unwrap() {
+        WaitStatus::Exited(_, 0) => true,
+        WaitStatus::Exited(_, 1) => false,
+        status => panic!("unexpected status {:?}", status),
+    };
+
+    let command = || {
+        let mut command = Command::new("sh");
+        command.args([
+            "-c",
+            "test -t 0 || exit 2; if (: < /dev/tty) 2>/dev/null; then exit 0; else exit 1; fi",
+        ]);
+        command
+    };
+
+    assert!(has_ctty(PtyProcess::spawn(command()).unwrap()));
+    assert!(!has_ctty(PtyProcess::spawn_no_ctty(command()).unwrap()));
+}
+
+#[test]
+fn slave_open_flags() {
+    use nix::fcntl::OFlag;
+    use ptyprocess::{PtyProcessBuilder, WaitStatus};
+
+    // the PTY is still made a controlling terminal
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        "test -t 0 || exit 2; if (: < /dev/tty) 2>/dev/null; then exit 0; else exit 1; fi",
+    ]);
+    let proc = PtyProcessBuilder::new()
+        .command(command)
+        .slave_open_flags(OFlag::O_RDWR | OFlag::O_NOCTTY)
+        .build()
+        .unwrap();
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[test]
+fn reset_signals() {
+    use nix::sys::signal::{self, SigHandler};
+    use ptyprocess::{PtyProcessBuilder, Signal, WaitStatus};
+
+    // the test process ignores SIGUSR2, so it's inherited by a child unless reset
+    unsafe { signal::signal(Signal::SIGUSR2, SigHandler::SigIgn) }.unwrap();
+
+    let spawn = |reset| {
+        let mut command = Command::new("sh");
+        command.args(["-c", "kill -USR2 $$; exit 0"]);
+        let proc = PtyProcessBuilder::new()
+            .command(command)
+            .reset_signals(reset)
+            .build()
+            .unwrap();
+        proc.wait().unwrap()
+    };
+
+    assert!(matches!(spawn(false), WaitStatus::Exited(_, 0)));
+    assert!(matches!(
+        spawn(true),
+        WaitStatus::Signaled(_, Signal::SIGUSR2, _)
+    ));
+}
+
+#[test]
+fn spawn_fn() {
+    use ptyprocess::WaitStatus;
+    use std::io::IsTerminal;
+
+    let proc = unsafe {
+        PtyProcess::spawn_fn(|| {
+            if io::stdin().is_terminal() && io::stdout().is_terminal() {
+                3
+            } else {
+                1
+            }
+        })
+    }
+    .unwrap();
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 3));
+}
+
+#[test]
+fn spawn_fn_panic() {
+    use ptyprocess::WaitStatus;
+
+    let proc = unsafe { PtyProcess::spawn_fn(|| panic!("closure panicked")) }.unwrap();
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 101));
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn spawn_fn_output() {
+    use std::io::{Read, Write};
+
+    let mut proc = unsafe {
+        PtyProcess::spawn_fn(|| {
+            io::stdout().write_all(b"Hello World\n").unwrap();
+            io::stdout().flush().unwrap();
+            0
+        })
+    }
+    .unwrap();
+
+    let mut buf = String::new();
+    proc.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "Hello World\r\n");
+}
+
+#[test]
+fn spawn_with_observer() {
+    use nix::errno::Errno;
+    use ptyprocess::SpawnEvent;
+
+    let mut events = Vec::new();
+    let proc =
+        PtyProcess::spawn_with_observer(Command::new("true"), &mut |event| events.push(event))
+            .unwrap();
+    assert_eq!(events, [SpawnEvent::Forked(proc.pid()), SpawnEvent::Ready]);
+
+    let mut events = Vec::new();
+    let err =
+        PtyProcess::spawn_with_observer(Command::new("/dev/null"), &mut |event| events.push(event))
+            .unwrap_err();
+    assert!(matches!(err, PtyError::Exec(_)));
+    assert!(matches!(
+        events.as_slice(),
+        [SpawnEvent::Forked(_), SpawnEvent::ExecFailed(Errno::EACCES)]
+    ));
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn spawn_clean_env() {
+    use std::io::Read;
+
+    // cargo sets it for a test, so it's inherited unless the environment is cleared
+    assert!(std::env::var_os("CARGO_PKG_NAME").is_some());
+
+    let mut command = Command::new("env");
+    command.env("PTYPROCESS_TEST_EXPLICIT", "explicit");
+    command.env_remove("LANG");
+    let mut proc = PtyProcess::spawn_clean_env(command, "vt100").unwrap();
+
+    let mut output = String::new();
+    proc.read_to_string(&mut output).unwrap();
+    let vars = output.lines().collect::<Vec<_>>();
+
+    assert!(vars.contains(&"TERM=vt100"), "{:?}", vars);
+    assert!(
+        vars.contains(&"PTYPROCESS_TEST_EXPLICIT=explicit"),
+        "{:?}",
+        vars
+    );
+    assert!(vars.iter().all(|var| !var.starts_with("CARGO_PKG_NAME=")));
+    assert!(vars.iter().all(|var| !var.starts_with("LANG=")));
+    if let Ok(path) = std::env::var("PATH") {
+        assert!(vars.contains(&format!("PATH={}", path).as_str()));
+    }
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn spawn_split_stderr() {
+    use std::io::Read;
+
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        "echo out; echo err >&2; test -t 2 && echo tty >&2; exit 0",
+    ]);
+    let mut proc = PtyProcess::spawn_split_stderr(command).unwrap();
+
+    let mut stderr = proc.take_stderr().unwrap();
+    assert!(proc.stderr().is_none());
+
+    let mut output = String::new();
+    proc.read_to_string(&mut output).unwrap();
+    assert_eq!(output, "out\r\n");
+
+    let mut errors = String::new();
+    stderr.read_to_string(&mut errors).unwrap();
+    assert_eq!(errors, "err\n");
+
+    // by default stderr goes to the PTY
+    let mut proc = PtyProcess::spawn(Command::new("true")).unwrap();
+    assert!(proc.stderr().is_none());
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn spawn_in_dir() {
+    use ptyprocess::{PtyProcessBuilder, SpawnStage};
+    use std::{io::Read, path::Path};
+
+    let pwd = |mut proc: PtyProcess| {
+        let mut output = String::new();
+        proc.read_to_string(&mut output).unwrap();
+        output
+    };
+
+    let proc = PtyProcess::spawn_in_dir(Command::new("pwd"), Path::new("/")).unwrap();
+    assert_eq!(pwd(proc), "/\r\n");
+
+    // a directory of a command is applied as well
+    let mut command = Command::new("pwd");
+    command.current_dir("/");
+    let proc = PtyProcess::spawn(command).unwrap();
+    assert_eq!(pwd(proc), "/\r\n");
+
+    let err = PtyProcessBuilder::new()
+        .command(Command::new("pwd"))
+        .current_dir("/a/directory/which/does/not/exist")
+        .build()
+        .unwrap_err();
+    match err {
+        PtyError::ChildSetup(SpawnStage::WorkingDirectory, err) => {
+            assert_eq!(err.kind(), io::ErrorKind::NotFound)
+        }
+        err => panic!("unexpected error {:?}", err),
+    }
+}
@@ -1,4 +1,6 @@
-use ptyprocess::{ControlCode, PtyProcess, Signal, WaitStatus};
+use ptyprocess::{
+    BufferedPtyProcess, ControlCode, LineEnding, PtyError, PtyProcess, Signal, WaitStatus,
+};
 use std::{process::Command, thread, time::Duration};
 
 #[cfg(feature = "async")]
@@ -26,6 +28,249 @@ fn custom_reader_writer() {
     assert!(proc.exit(true).unwrap());
 }
 
+#[cfg(feature = "sync")]
+#[test]
+fn pty_handle_outlives_process() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let mut pty = proc.get_pty_handle().unwrap();
+
+    writeln!(proc, "hello cat").unwrap();
+    let mut buf = String::new();
+    proc.read_line(&mut buf).unwrap();
+    assert_eq!(buf, "hello cat\r\n");
+
+    drop(proc);
+
+    // the descriptor is still valid but a child is gone
+    let mut buf = Vec::new();
+    match pty.read_to_end(&mut buf) {
+        Ok(_) => {}
+        Err(err) => assert_eq!(err.raw_os_error(), Some(nix::libc::EIO)),
+    }
+    assert!(buf.is_empty());
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn read_write_through_shared_reference() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    thread::scope(|scope| {
+        let reader = scope.spawn(|| {
+            let mut reader = BufReader::new(&proc);
+            let mut buf = String::new();
+            reader.read_line(&mut buf).unwrap();
+            buf
+        });
+
+        scope.spawn(|| {
+            let mut writer = &proc;
+            writer.write_all(b"hello cat\n").unwrap();
+        });
+
+        assert_eq!(reader.join().unwrap(), "hello cat\r\n");
+    });
+
+    let mut proc = proc;
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn split() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let (mut reader, mut writer) = proc.split().unwrap();
+
+    #[cfg(feature = "sync")]
+    {
+        let handle = thread::spawn(move || {
+            writer.write_all(b"hello cat\n").unwrap();
+            writer.flush().unwrap();
+        });
+
+        let mut buf = String::new();
+        reader.read_line(&mut buf).unwrap();
+        assert_eq!(buf, "hello cat\r\n");
+
+        // the writer is dropped in the thread,
+        // which must not affect the reader.
+        handle.join().unwrap();
+
+        buf.clear();
+        p_write_all(&mut proc, b"hello again\n").unwrap();
+        reader.read_line(&mut buf).unwrap();
+        assert_eq!(buf, "hello again\r\n");
+    }
+    #[cfg(feature = "async")]
+    block_on(async {
+        writer.write_all(b"hello cat\n").await.unwrap();
+        writer.flush().await.unwrap();
+        drop(writer);
+
+        let mut buf = String::new();
+        reader.read_line(&mut buf).await.unwrap();
+        assert_eq!(buf, "hello cat\r\n");
+    });
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn spawn_keep_slave() {
+    use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+    use std::os::unix::io::AsRawFd;
+
+    let mut cmd = Command::new("echo");
+    cmd.arg("Hello World");
+    let (mut proc, slave) = PtyProcess::spawn_keep_slave(cmd).unwrap();
+
+    // it's not inherited by processes spawned later
+    let flags = fcntl(slave.as_raw_fd(), FcntlArg::F_GETFD).unwrap();
+    assert!(FdFlag::from_bits_truncate(flags).contains(FdFlag::FD_CLOEXEC));
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "Hello World\r\n");
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+
+    // the PTY is not torn down while the slave is opened
+    let mut buf = [0; 128];
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), None);
+
+    drop(slave);
+
+    assert_eq!(p_read_to_end(&mut proc).unwrap(), b"");
+}
+
+#[test]
+fn try_write() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert_eq!(p_try_write(&mut proc, b"hello cat\n").unwrap(), Some(10));
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn try_write_would_block() {
+    let mut command = Command::new("sleep");
+    command.arg("5");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    proc.set_raw_mode().unwrap();
+
+    // nobody reads an input so it eventually gets full.
+    let buf = [b'a'; 1024];
+    let mut written = 0;
+    while let Some(n) = p_try_write(&mut proc, &buf).unwrap() {
+        written += n;
+        assert!(written < 1024 * 1024, "a write never blocks");
+    }
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn push_to_input() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    match proc.push_to_input(b"hello cat\n") {
+        Ok(()) => {}
+        // TIOCSTI may be restricted on the system
+        Err(PtyError::Io(err)) if err.kind() == std::io::ErrorKind::PermissionDenied => return,
+        Err(err) => panic!("unexpected error {}", err),
+    }
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn send_break() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.send_break(None).unwrap();
+    proc.send_break(Some(Duration::from_millis(100))).unwrap();
+
+    p_send_line(&mut proc, "hello cat").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn wait_output_drained() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_line(&mut proc, "hello cat").unwrap();
+    proc.wait_output_drained().unwrap();
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn discard_input() {
+    let mut proc = PtyProcess::spawn_with_echo(Command::new("cat"), true).unwrap();
+
+    // cat is stopped so it can't read the input before it's discarded
+    proc.kill(Signal::SIGSTOP).unwrap();
+    wait_stopped(&proc);
+
+    p_send_line(&mut proc, "hello").unwrap();
+    // the echo means the line is in the input queue
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello\r\n");
+
+    proc.discard_input().unwrap();
+    proc.resume().unwrap();
+
+    p_send_line(&mut proc, "world").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "world\r\n");
+    assert_eq!(p_read_line(&mut proc).unwrap(), "world\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn discard_output() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "echo hello; exec cat"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    wait_pty_readable(&proc);
+    proc.discard_output().unwrap();
+
+    let mut buf = [0; 128];
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), None);
+
+    p_send_line(&mut proc, "world").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "world\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn discard_io() {
+    let mut proc = PtyProcess::spawn_with_echo(Command::new("cat"), true).unwrap();
+
+    proc.kill(Signal::SIGSTOP).unwrap();
+    wait_stopped(&proc);
+
+    p_send_line(&mut proc, "hello").unwrap();
+    // the echo means the line is in the input queue and the echo is in the output one
+    wait_pty_readable(&proc);
+    proc.discard_io().unwrap();
+    proc.resume().unwrap();
+
+    let mut buf = [0; 128];
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), None);
+
+    p_send_line(&mut proc, "world").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "world\r\n");
+    assert_eq!(p_read_line(&mut proc).unwrap(), "world\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
 #[test]
 fn cat_intr() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -44,6 +289,62 @@ fn cat_intr() {
     );
 }
 
+#[test]
+fn send_stop_start() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_stop(&mut proc).unwrap();
+    p_send_line(&mut proc, "hello cat").unwrap();
+
+    // give cat a time to react on input
+    thread::sleep(Duration::from_millis(300));
+
+    let mut buf = [0; 128];
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), None);
+
+    p_send_start(&mut proc).unwrap();
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn cat_quit() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    // give cat a time to be ready to take the ^\\
+    thread::sleep(Duration::from_millis(300));
+
+    p_send_quit(&mut proc).unwrap();
+
+    match proc.wait().unwrap() {
+        WaitStatus::Signaled(_, Signal::SIGQUIT, _) => {}
+        status => panic!("unexpected status {:?}", status),
+    }
+}
+
+#[test]
+fn send_susp() {
+    // a child's process group is orphaned so SIGTSTP doesn't stop it by default,
+    // therefore we check that the signal is delivered by a handler.
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        "trap 'echo SIGTSTP' TSTP; while :; do sleep 0.1; done",
+    ]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    // give the shell a time to set a trap
+    thread::sleep(Duration::from_millis(300));
+
+    p_send_susp(&mut proc).unwrap();
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "SIGTSTP\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
 #[test]
 fn cat_eof() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -84,6 +385,61 @@ fn ptyprocess_check_terminal_line_settings() {
     assert!(buf.split_whitespace().any(|word| word == "-echo"));
 }
 
+#[test]
+fn ptyprocess_check_terminal_line_settings_with_echo() {
+    let mut command = Command::new("stty");
+    command.arg("-a");
+    let mut proc = PtyProcess::spawn_with_echo(command, true).unwrap();
+
+    let buf = p_read_to_string(&mut proc).unwrap();
+
+    assert!(buf.split_whitespace().any(|word| word == "echo"));
+}
+
+#[test]
+fn send_raw_control() {
+    use std::convert::TryFrom;
+
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_raw_control(&mut proc, 0x1b).unwrap();
+    p_send_line(&mut proc, "").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "\x1b\r\n");
+
+    let err = p_send_raw_control(&mut proc, b'a').unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    // a raw byte is accepted by send_control as well
+    p_send_control(&mut proc, ControlCode::try_from(0x04).unwrap()).unwrap();
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[test]
+fn send_ansi() {
+    use ptyprocess::AnsiSequence;
+
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_ansi(&mut proc, AnsiSequence::CursorTo(2, 5)).unwrap();
+    p_send_ansi(&mut proc, AnsiSequence::ClearLine).unwrap();
+    p_send_line(&mut proc, "").unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "\x1b[2;5H\x1b[2K\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn send_throttled() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let now = std::time::Instant::now();
+    p_send_throttled(&mut proc, b"abc\n", Duration::from_millis(50)).unwrap();
+    assert!(now.elapsed() >= Duration::from_millis(150));
+    assert_eq!(p_read_line(&mut proc).unwrap(), "abc\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
 #[test]
 fn send_controll() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -93,6 +449,26 @@ fn send_controll() {
     assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0),);
 }
 
+#[test]
+fn send_eof_is_not_a_close() {
+    // canonical mode turns it into EOF
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    p_send_eof(&mut proc).unwrap();
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+
+    // raw mode passes it as is
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    proc.set_raw_mode().unwrap();
+    p_send_eof(&mut proc).unwrap();
+
+    let mut buf = [0; 1];
+    p_read_exact(&mut proc, &mut buf).unwrap();
+    assert_eq!(buf, [0x4]);
+    assert!(proc.is_alive().unwrap());
+
+    assert!(proc.exit(true).unwrap());
+}
+
 #[test]
 fn send() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -125,6 +501,20 @@ fn send_line() {
     assert!(proc.exit(true).unwrap());
 }
 
+#[test]
+fn send_line_with() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    // a terminal translates CR into NL on input,
+    // so CRLF is seen by cat as 2 lines.
+    p_send_line_with(&mut proc, "hello cat", LineEnding::CrLf).unwrap();
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), "hello cat\r\n");
+    assert_eq!(p_read_line(&mut proc).unwrap(), "\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
 #[test]
 fn try_read_byte() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -271,61 +661,583 @@ fn read_line() {
 }
 
 #[test]
-fn read_until() {
+fn try_read_after_buffered_read() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
 
     p_send_line(&mut proc, "Hello World 1").unwrap();
+    p_send_line(&mut proc, "Hello World 2").unwrap();
 
     // give cat a time to react on input
     thread::sleep(Duration::from_millis(100));
 
-    assert_eq!(p_read_until(&mut proc, b' ').unwrap(), b"Hello ");
+    assert_eq!(p_read_line(&mut proc).unwrap(), "Hello World 1\r\n");
 
     let mut buf = vec![0; 128];
-    let n = p_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf[..n], b"World 1\r\n");
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), Some(15));
+    assert_eq!(&buf[..15], b"Hello World 2\r\n");
 
     assert!(proc.exit(true).unwrap());
 }
 
 #[test]
-fn read_to_end() {
-    let mut cmd = Command::new("echo");
-    cmd.arg("Hello World");
-    let mut proc = PtyProcess::spawn(cmd).unwrap();
+fn buffered() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap().buffered();
 
-    assert_eq!(p_read_to_end(&mut proc).unwrap(), b"Hello World\r\n");
-}
+    p_send_line(&mut proc, "Hello World 1").unwrap();
+    p_send_line(&mut proc, "Hello World 2").unwrap();
 
-#[cfg(not(target_os = "macos"))]
-#[test]
-fn read_to_end_after_delay() {
-    let mut cmd = Command::new("echo");
-    cmd.arg("Hello World");
-    let mut proc = PtyProcess::spawn(cmd).unwrap();
+    assert_eq!(
+        p_buffered_read_line(&mut proc).unwrap(),
+        "Hello World 1\r\n"
+    );
 
-    thread::sleep(Duration::from_millis(500));
+    // the rest is either in the buffer or in the PTY
+    let mut proc = proc.into_inner();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "Hello World 2\r\n");
 
-    assert_eq!(p_read_to_end(&mut proc).unwrap(), b"Hello World\r\n");
+    let mut buf = [0; 128];
+    assert_eq!(p_try_read(&mut proc, &mut buf).unwrap(), None);
+
+    assert!(proc.exit(true).unwrap());
 }
 
-#[cfg(not(target_os = "macos"))]
 #[test]
-fn read_after_process_is_gone() {
-    let mut cmd = Command::new("echo");
-    cmd.arg("Hello World");
-    let mut proc = PtyProcess::spawn(cmd).unwrap();
+fn read_until() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
 
-    // after we check a status of child
-    // it should be marked DEAD.
-    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+    p_send_line(&mut proc, "Hello World 1").unwrap();
 
-    // Just in case; make a little delay
-    thread::sleep(Duration::from_millis(500));
+    // give cat a time to react on input
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(p_read_until(&mut proc, b' ').unwrap(), b"Hello ");
 
     let mut buf = vec![0; 128];
     let n = p_read(&mut proc, &mut buf).unwrap();
-    assert_eq!(&buf[..n], b"Hello World\r\n");
+    assert_eq!(&buf[..n], b"World 1\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn take_stdin_and_stdout() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.send_line("Hello").unwrap();
+    // the data is buffered by the process
+    assert!(proc.has_data_ready(Some(Duration::from_secs(1))).unwrap());
+    let mut buf = [0; 1];
+    proc.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"H");
+
+    let mut stdin = proc.take_stdin().unwrap().unwrap();
+    let mut stdout = proc.take_stdout().unwrap().unwrap();
+    assert!(proc.take_stdin().unwrap().is_none());
+    assert!(proc.take_stdout().unwrap().is_none());
+
+    let mut line = String::new();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "ello\r\n");
+
+    let writer = thread::spawn(move || stdin.write_all(b"World\n").unwrap());
+    writer.join().unwrap();
+
+    line.clear();
+    stdout.read_line(&mut line).unwrap();
+    assert_eq!(line, "World\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn scrollback_reader() {
+    use ptyprocess::ScrollbackReader;
+
+    let mut command = Command::new("seq");
+    command.arg("100000");
+    let proc = PtyProcess::spawn(command).unwrap();
+
+    // the output is much larger than a PTY buffer,
+    // so the child would block if it's not read
+    let scrollback = ScrollbackReader::new(proc.get_pty_handle().unwrap(), 16);
+    assert_eq!(
+        proc.wait_timeout(Duration::from_secs(10)).unwrap(),
+        Some(WaitStatus::Exited(proc.pid(), 0))
+    );
+
+    assert_eq!(scrollback.join().unwrap(), b"\n99999\r\n100000\r\n");
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn chunk_reader() {
+    use ptyprocess::ChunkReader;
+
+    // sequences are split between writes
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        r"printf 'a\033[3'; sleep 0.2; printf '1mb\303'; sleep 0.2; printf '\251c\033'",
+    ]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    let mut reader = ChunkReader::new(proc.take_stdout().unwrap().unwrap());
+
+    let mut chunks = Vec::new();
+    while !reader.is_eof() {
+        match reader.next_chunk().unwrap() {
+            Some(chunk) => chunks.push(chunk.to_vec()),
+            None => thread::sleep(Duration::from_millis(10)),
+        }
+    }
+
+    // chunk boundaries depend on timings, so only the data is checked
+    assert_eq!(chunks.concat(), "a\x1b[31mbéc\x1b".as_bytes());
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn set_read_timeout() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    proc.set_read_timeout(Some(Duration::from_millis(300)));
+
+    let mut buf = [0; 128];
+    let now = std::time::Instant::now();
+    let err = proc.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(now.elapsed() >= Duration::from_millis(250));
+
+    proc.send_line("hello").unwrap();
+    let mut line = String::new();
+    proc.read_line(&mut line).unwrap();
+    assert_eq!(line, "hello\r\n");
+
+    proc.set_read_timeout(None);
+    proc.send_line("world").unwrap();
+    line.clear();
+    proc.read_line(&mut line).unwrap();
+    assert_eq!(line, "world\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn lines() {
+    let mut command = Command::new("printf");
+    command.arg("hello\\n\\nworld\\r\\nlast");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    let lines = proc.lines().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(lines, ["hello", "", "world\r", "last"]);
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn lines_invalid_utf8() {
+    let mut command = Command::new("printf");
+    command.arg("hello\\n\\377world\\nlast");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    let lines = proc.lines().collect::<std::io::Result<Vec<_>>>().unwrap();
+    assert_eq!(lines, ["hello", "\u{FFFD}world", "last"]);
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[test]
+fn read_to_vec() {
+    let mut command = Command::new("seq");
+    command.arg("10000");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    let expected = (1..=10000)
+        .map(|i| format!("{}\r\n", i))
+        .collect::<String>();
+    let buf = p_read_to_vec(&mut proc, expected.len()).unwrap();
+    assert_eq!(buf, expected.as_bytes());
+
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[test]
+fn drain() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert_eq!(p_drain(&mut proc).unwrap(), b"");
+
+    p_send_line(&mut proc, "Hello World 1").unwrap();
+    p_send_line(&mut proc, "Hello World 2").unwrap();
+
+    // give cat a time to react on input
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(
+        p_drain(&mut proc).unwrap(),
+        b"Hello World 1\r\nHello World 2\r\n"
+    );
+    assert_eq!(p_drain(&mut proc).unwrap(), b"");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn set_nonblocking() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    proc.set_nonblocking(true).unwrap();
+
+    let mut buf = [0; 128];
+    let err = proc.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+    // try_read must keep the mode
+    assert_eq!(proc.try_read(&mut buf).unwrap(), None);
+    let err = proc.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WouldBlock);
+
+    proc.send_line("hello cat").unwrap();
+    proc.set_nonblocking(false).unwrap();
+
+    let mut line = String::new();
+    proc.read_line(&mut line).unwrap();
+    assert_eq!(line, "hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn send_line_expect() {
+    for echo in [false, true] {
+        let mut command = Command::new("sh");
+        command.env("PS1", "PROMPT> ");
+        let mut proc = PtyProcess::spawn_with_echo(command, echo).unwrap();
+
+        let timeout = Some(Duration::from_secs(3));
+        p_read_until_match(&mut proc, b"PROMPT> ", timeout)
+            .unwrap()
+            .unwrap();
+
+        let output =
+            p_send_line_expect(&mut proc, "echo Hello World", b"PROMPT> ", timeout).unwrap();
+        assert_eq!(output, b"Hello World\r\n", "echo={}", echo);
+
+        let err = p_send_line_expect(
+            &mut proc,
+            "sleep 5",
+            b"PROMPT> ",
+            Some(Duration::from_millis(300)),
+        )
+        .unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+        assert!(proc.exit(true).unwrap());
+    }
+}
+
+#[cfg(feature = "sync")]
+#[test]
+fn read_available_string() {
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        r"printf '\377a\303'; sleep 0.5; printf '\251b'; sleep 5",
+    ]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    thread::sleep(Duration::from_millis(250));
+    assert_eq!(proc.read_available_string().unwrap(), "\u{FFFD}a");
+
+    thread::sleep(Duration::from_millis(500));
+    assert_eq!(proc.read_available_string().unwrap(), "\u{e9}b");
+    assert_eq!(proc.read_available_string().unwrap(), "");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn drain_into_and_read_until_into() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let mut buf = b"prefix ".to_vec();
+    assert_eq!(p_drain_into(&mut proc, &mut buf).unwrap(), 0);
+
+    p_send_line(&mut proc, "Hello World 1").unwrap();
+    thread::sleep(Duration::from_millis(100));
+
+    assert_eq!(p_drain_into(&mut proc, &mut buf).unwrap(), 15);
+    assert_eq!(buf, b"prefix Hello World 1\r\n");
+
+    buf.clear();
+    p_send(&mut proc, "Hello World 2\nHello").unwrap();
+
+    let timeout = Some(Duration::from_millis(300));
+    assert_eq!(
+        p_read_until_into(&mut proc, b'\n', &mut buf, timeout).unwrap(),
+        15
+    );
+    // cat doesn't get an unterminated line
+    assert_eq!(
+        p_read_until_into(&mut proc, b'\n', &mut buf, timeout).unwrap(),
+        0
+    );
+    assert_eq!(buf, b"Hello World 2\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn read_until_timeout() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send(&mut proc, "Hello World 1\nHello").unwrap();
+
+    let timeout = Some(Duration::from_millis(300));
+    assert_eq!(
+        p_read_until_timeout(&mut proc, b'\n', timeout).unwrap(),
+        b"Hello World 1\r\n"
+    );
+    assert_eq!(
+        p_read_until_timeout(&mut proc, b'\n', timeout).unwrap(),
+        b""
+    );
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn read_exact_timeout() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send(&mut proc, "Hello World\nHello").unwrap();
+
+    let timeout = Duration::from_millis(300);
+    let mut buf = [0; 13];
+    p_read_exact_timeout(&mut proc, &mut buf, timeout).unwrap();
+    assert_eq!(&buf, b"Hello World\r\n");
+
+    let mut buf = [0; 13];
+    let err = p_read_exact_timeout(&mut proc, &mut buf, timeout).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::TimedOut);
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn expect_silence() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert!(p_expect_silence(&mut proc, Duration::from_millis(300)).unwrap());
+
+    p_send_line(&mut proc, "Hello World").unwrap();
+
+    assert!(!p_expect_silence(&mut proc, Duration::from_millis(300)).unwrap());
+    assert_eq!(p_read_line(&mut proc).unwrap(), "Hello World\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn read_until_match() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_line(&mut proc, "Hello World").unwrap();
+    p_send_line(&mut proc, "$ rest").unwrap();
+
+    let timeout = Some(Duration::from_millis(300));
+    assert_eq!(
+        p_read_until_match(&mut proc, b"$ ", timeout).unwrap(),
+        Some(b"Hello World\r\n$ ".to_vec())
+    );
+    assert_eq!(p_read_until_match(&mut proc, b"$ ", timeout).unwrap(), None);
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn wait_for_any() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    p_send_line(&mut proc, "user$ root# rest").unwrap();
+
+    let prompts: [&[u8]; 3] = [b"# ", b"$ ", b"r$ "];
+    let timeout = Some(Duration::from_millis(300));
+    assert_eq!(
+        p_wait_for_any(&mut proc, &prompts, timeout).unwrap(),
+        Some((1, b"user$ ".to_vec()))
+    );
+    assert_eq!(
+        p_wait_for_any(&mut proc, &prompts, timeout).unwrap(),
+        Some((0, b"root# ".to_vec()))
+    );
+    assert_eq!(p_wait_for_any(&mut proc, &prompts, timeout).unwrap(), None);
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn wait_for_any_short_timeout() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let prompts: [&[u8]; 1] = [b"$ "];
+    let timeout = Some(Duration::from_nanos(1));
+    for _ in 0..100 {
+        assert_eq!(p_wait_for_any(&mut proc, &prompts, timeout).unwrap(), None);
+    }
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn spawn_shell() {
+    let term = std::env::var("TERM")
+        .ok()
+        .filter(|term| !term.is_empty())
+        .unwrap_or_else(|| "xterm-256color".to_owned());
+
+    let mut proc = PtyProcess::spawn_shell().unwrap();
+    p_send_line(&mut proc, "echo \"TERM is $TERM\"; exit").unwrap();
+
+    let output = p_read_to_string(&mut proc).unwrap();
+    assert!(
+        output.contains(&format!("TERM is {}\r\n", term)),
+        "{:?}",
+        output
+    );
+}
+
+#[test]
+fn forward_winch() {
+    use std::os::unix::io::AsRawFd;
+
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        "trap 'stty size; exit' WINCH; echo ready; while :; do sleep 0.1; done",
+    ]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "ready\r\n");
+
+    // the size is the same so a kernel doesn't send SIGWINCH on its own
+    let terminal = PtyProcess::spawn(Command::new("cat")).unwrap();
+    proc.forward_winch(terminal.as_raw_fd()).unwrap();
+    assert_eq!(p_read_line(&mut proc).unwrap(), "24 80\r\n");
+}
+
+#[test]
+fn bytes_available() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert_eq!(proc.bytes_available().unwrap(), 0);
+
+    p_send_line(&mut proc, "Hello World").unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(proc.bytes_available().unwrap(), 13);
+
+    // buffered bytes are counted as well
+    let mut buf = [0; 5];
+    p_read_exact(&mut proc, &mut buf).unwrap();
+    assert_eq!(proc.bytes_available().unwrap(), 8);
+
+    assert_eq!(p_read_line(&mut proc).unwrap(), " World\r\n");
+    assert_eq!(proc.bytes_available().unwrap(), 0);
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn has_data_ready() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let timeout = Some(Duration::from_millis(100));
+    assert!(!proc.has_data_ready(timeout).unwrap());
+    assert!(!proc.has_data_ready(Some(Duration::ZERO)).unwrap());
+
+    p_send_line(&mut proc, "Hello World").unwrap();
+    assert!(proc.has_data_ready(Some(Duration::from_secs(3))).unwrap());
+
+    // a part of a line is left in a buffer
+    let mut buf = [0; 5];
+    p_read_exact(&mut proc, &mut buf).unwrap();
+    assert!(proc.has_data_ready(Some(Duration::ZERO)).unwrap());
+    assert_eq!(p_read_line(&mut proc).unwrap(), " World\r\n");
+
+    assert!(!proc.has_data_ready(timeout).unwrap());
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn stream_capacity() {
+    let mut command = Command::new("echo");
+    command.arg("Hello World, it's a line longer than the buffer");
+    let mut proc = ptyprocess::PtyProcessBuilder::new()
+        .command(command)
+        .stream_capacity(4)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        p_read_to_string(&mut proc).unwrap(),
+        "Hello World, it's a line longer than the buffer\r\n"
+    );
+}
+
+#[test]
+fn stream_capacity_zero() {
+    let mut command = Command::new("echo");
+    command.arg("Hello World");
+    let mut proc = ptyprocess::PtyProcessBuilder::new()
+        .command(command)
+        .stream_capacity(0)
+        .build()
+        .unwrap();
+
+    // an empty buffer would be taken for EOF
+    assert_eq!(p_read_line(&mut proc).unwrap(), "Hello World\r\n");
+}
+
+#[test]
+fn read_to_end() {
+    let mut cmd = Command::new("echo");
+    cmd.arg("Hello World");
+    let mut proc = PtyProcess::spawn(cmd).unwrap();
+
+    assert_eq!(p_read_to_end(&mut proc).unwrap(), b"Hello World\r\n");
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn read_to_end_after_delay() {
+    let mut cmd = Command::new("echo");
+    cmd.arg("Hello World");
+    let mut proc = PtyProcess::spawn(cmd).unwrap();
+
+    thread::sleep(Duration::from_millis(500));
+
+    assert_eq!(p_read_to_end(&mut proc).unwrap(), b"Hello World\r\n");
+}
+
+#[cfg(not(target_os = "macos"))]
+#[test]
+fn read_after_process_is_gone() {
+    let mut cmd = Command::new("echo");
+    cmd.arg("Hello World");
+    let mut proc = PtyProcess::spawn(cmd).unwrap();
+
+    // after we check a status of child
+    // it should be marked DEAD.
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 0));
+
+    // Just in case; make a little delay
+    thread::sleep(Duration::from_millis(500));
+
+    let mut buf = vec![0; 128];
+    let n = p_read(&mut proc, &mut buf).unwrap();
+    assert_eq!(&buf[..n], b"Hello World\r\n");
 }
 
 #[cfg(not(target_os = "macos"))]
@@ -354,63 +1266,234 @@ fn try_read_to_end() {
     let mut buf = vec![0; 128];
     while p_try_read(&mut proc, &mut buf).unwrap() != Some(0) {}
 
-    assert_eq!(&buf[..13], b"Hello World\r\n");
-}
+    assert_eq!(&buf[..13], b"Hello World\r\n");
+}
+
+#[test]
+fn continues_try_reads() {
+    let mut cmd = Command::new("python3");
+    cmd.args(vec![
+        "-c",
+        "import time;\
+        print('Start Sleep');\
+        time.sleep(0.1);\
+        print('End of Sleep');\
+        yn=input('input');",
+    ]);
+
+    let mut proc = PtyProcess::spawn(cmd).unwrap();
+
+    let mut buf = [0; 128];
+    loop {
+        if let Some(n) = p_try_read(&mut proc, &mut buf).unwrap() {
+            if String::from_utf8_lossy(&buf[..n]).contains("input") {
+                break;
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(not(target_os = "macos"))]
+fn automatic_stop_of_interact() {
+    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
+    let status = p_interact(&mut p).unwrap();
+
+    // It may be finished not only because process is done but
+    // also because it reached EOF.
+    assert!(matches!(
+        status,
+        WaitStatus::Exited(_, 0) | WaitStatus::StillAlive
+    ));
+
+    // check that second spawn works
+    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
+    let status = p_interact(&mut p).unwrap();
+    assert!(matches!(
+        status,
+        WaitStatus::Exited(_, 0) | WaitStatus::StillAlive
+    ));
+}
+
+#[test]
+#[cfg(not(target_os = "macos"))]
+fn spawn_after_interact() {
+    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
+    let _ = p_interact(&mut p).unwrap();
+
+    let p = PtyProcess::spawn(Command::new("ls")).unwrap();
+    assert!(matches!(p.wait().unwrap(), WaitStatus::Exited(_, 0)));
+}
+
+#[test]
+fn interact_io() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let input: &[u8] = b"hello cat\n\x1d";
+    let mut output = Vec::new();
+    let status = p_interact_io(&mut proc, input, &mut output).unwrap();
+    assert_eq!(status, WaitStatus::StillAlive);
+
+    // give cat a time to react on input
+    thread::sleep(Duration::from_millis(100));
+
+    let mut buf = vec![0; 128];
+    let n = p_read(&mut proc, &mut buf).unwrap();
+    assert!(String::from_utf8_lossy(&buf[..n]).contains("hello cat"));
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn interact_io_with_escape() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let input: &[u8] = b"hello cat\n\x01world\n";
+    let mut output = Vec::new();
+    let status =
+        p_interact_io_with_escape(&mut proc, input, &mut output, ControlCode::StartOfHeading)
+            .unwrap();
+    assert_eq!(status, WaitStatus::StillAlive);
+
+    // give cat a time to react on input
+    thread::sleep(Duration::from_millis(100));
+
+    let mut buf = vec![0; 128];
+    let n = p_read(&mut proc, &mut buf).unwrap();
+    output.extend_from_slice(&buf[..n]);
+
+    // nothing after the escape character is sent
+    let output = String::from_utf8_lossy(&output);
+    assert!(output.contains("hello cat"), "{:?}", output);
+    assert!(!output.contains("world"), "{:?}", output);
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn interact_io_poll_interval() {
+    struct PendingReader;
+
+    #[cfg(feature = "sync")]
+    impl std::io::Read for PendingReader {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::WouldBlock.into())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl futures_lite::AsyncRead for PendingReader {
+        fn poll_read(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            _: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            std::task::Poll::Pending
+        }
+    }
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "sleep 0.2"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    proc.set_interact_poll_interval(Duration::from_millis(50));
+
+    let mut output = Vec::new();
+    let status = p_interact_io(&mut proc, PendingReader, &mut output).unwrap();
+
+    // interaction is stopped on EOF which may be seen before a child can be reaped
+    let status = match status {
+        WaitStatus::StillAlive => proc.wait().unwrap(),
+        status => status,
+    };
+    assert!(matches!(status, WaitStatus::Exited(_, 0)));
+}
+
+#[test]
+fn interact_io_restores_echo_on_panic() {
+    struct PanicWriter;
+
+    #[cfg(feature = "sync")]
+    impl std::io::Write for PanicWriter {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            panic!("write failed")
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(feature = "async")]
+    impl futures_lite::AsyncWrite for PanicWriter {
+        fn poll_write(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            _: &[u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            panic!("write failed")
+        }
 
-#[test]
-fn continues_try_reads() {
-    let mut cmd = Command::new("python3");
-    cmd.args(vec![
-        "-c",
-        "import time;\
-        print('Start Sleep');\
-        time.sleep(0.1);\
-        print('End of Sleep');\
-        yn=input('input');",
-    ]);
+        fn poll_flush(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
 
-    let mut proc = PtyProcess::spawn(cmd).unwrap();
+        fn poll_close(
+            self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<std::io::Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
 
-    let mut buf = [0; 128];
-    loop {
-        if let Some(n) = p_try_read(&mut proc, &mut buf).unwrap() {
-            if String::from_utf8_lossy(&buf[..n]).contains("input") {
-                break;
+    // gives a line once and then never reaches EOF,
+    // so the interaction lasts until the line is echoed back.
+    struct OnceReader(Option<&'static [u8]>);
+
+    #[cfg(feature = "sync")]
+    impl std::io::Read for OnceReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match self.0.take() {
+                Some(data) => {
+                    buf[..data.len()].copy_from_slice(data);
+                    Ok(data.len())
+                }
+                None => Err(std::io::ErrorKind::WouldBlock.into()),
             }
         }
     }
-}
 
-#[test]
-#[cfg(not(target_os = "macos"))]
-fn automatic_stop_of_interact() {
-    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
-    let status = p_interact(&mut p).unwrap();
+    #[cfg(feature = "async")]
+    impl futures_lite::AsyncRead for OnceReader {
+        fn poll_read(
+            mut self: std::pin::Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+            buf: &mut [u8],
+        ) -> std::task::Poll<std::io::Result<usize>> {
+            match self.0.take() {
+                Some(data) => {
+                    buf[..data.len()].copy_from_slice(data);
+                    std::task::Poll::Ready(Ok(data.len()))
+                }
+                None => std::task::Poll::Pending,
+            }
+        }
+    }
 
-    // It may be finished not only because process is done but
-    // also because it reached EOF.
-    assert!(matches!(
-        status,
-        WaitStatus::Exited(_, 0) | WaitStatus::StillAlive
-    ));
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert!(!proc.get_echo().unwrap());
 
-    // check that second spawn works
-    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
-    let status = p_interact(&mut p).unwrap();
-    assert!(matches!(
-        status,
-        WaitStatus::Exited(_, 0) | WaitStatus::StillAlive
-    ));
-}
+    let input = OnceReader(Some(b"hello cat\n"));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        p_interact_io(&mut proc, input, PanicWriter)
+    }));
+    assert!(result.is_err());
 
-#[test]
-#[cfg(not(target_os = "macos"))]
-fn spawn_after_interact() {
-    let mut p = PtyProcess::spawn(Command::new("ls")).unwrap();
-    let _ = p_interact(&mut p).unwrap();
+    assert!(!proc.get_echo().unwrap());
 
-    let p = PtyProcess::spawn(Command::new("ls")).unwrap();
-    assert!(matches!(p.wait().unwrap(), WaitStatus::Exited(_, 0)));
+    assert!(proc.exit(true).unwrap());
 }
 
 fn p_read(proc: &mut PtyProcess, buf: &mut [u8]) -> std::io::Result<usize> {
@@ -468,6 +1551,116 @@ fn p_send_line(proc: &mut PtyProcess, buf: &str) -> std::io::Result<()> {
     }
 }
 
+fn p_send_line_with(proc: &mut PtyProcess, buf: &str, ending: LineEnding) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_line_with(buf, ending)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_line_with(buf, ending))
+    }
+}
+
+fn p_send_stop(proc: &mut PtyProcess) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_stop()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_stop())
+    }
+}
+
+fn p_send_start(proc: &mut PtyProcess) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_start()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_start())
+    }
+}
+
+fn p_send_quit(proc: &mut PtyProcess) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_quit()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_quit())
+    }
+}
+
+fn p_send_susp(proc: &mut PtyProcess) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_susp()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_susp())
+    }
+}
+
+fn p_try_write(proc: &mut PtyProcess, buf: &[u8]) -> std::io::Result<Option<usize>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.try_write(buf)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.try_write(buf))
+    }
+}
+
+fn p_send_eof(proc: &mut PtyProcess) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_eof()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_eof())
+    }
+}
+
+fn p_send_raw_control(proc: &mut PtyProcess, byte: u8) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_raw_control(byte)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_raw_control(byte))
+    }
+}
+
+fn p_send_throttled(proc: &mut PtyProcess, bytes: &[u8], delay: Duration) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_throttled(bytes, delay)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_throttled(bytes, delay))
+    }
+}
+
+fn p_send_ansi(proc: &mut PtyProcess, seq: ptyprocess::AnsiSequence) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_ansi(seq)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_ansi(seq))
+    }
+}
+
 fn p_send_control(proc: &mut PtyProcess, buf: impl Into<ControlCode>) -> std::io::Result<()> {
     #[cfg(feature = "sync")]
     {
@@ -520,6 +1713,19 @@ fn p_read_until(proc: &mut PtyProcess, ch: u8) -> std::io::Result<Vec<u8>> {
     Ok(buf)
 }
 
+fn p_buffered_read_line(proc: &mut BufferedPtyProcess) -> std::io::Result<String> {
+    let mut buf = String::new();
+    #[cfg(feature = "sync")]
+    {
+        BufRead::read_line(proc, &mut buf)?;
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(AsyncBufReadExt::read_line(proc, &mut buf))?;
+    }
+    Ok(buf)
+}
+
 fn p_read_line(proc: &mut PtyProcess) -> std::io::Result<String> {
     let mut buf = String::new();
     #[cfg(feature = "sync")]
@@ -533,6 +1739,17 @@ fn p_read_line(proc: &mut PtyProcess) -> std::io::Result<String> {
     Ok(buf)
 }
 
+fn p_read_exact(proc: &mut PtyProcess, buf: &mut [u8]) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_exact(buf)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_exact(buf))
+    }
+}
+
 fn p_try_read_byte(proc: &mut PtyProcess) -> std::io::Result<Option<Option<u8>>> {
     #[cfg(feature = "sync")]
     {
@@ -555,6 +1772,142 @@ fn p_try_read(proc: &mut PtyProcess, buf: &mut [u8]) -> std::io::Result<Option<u
     }
 }
 
+fn p_read_to_vec(proc: &mut PtyProcess, hint: usize) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_to_vec(hint)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_to_vec(hint))
+    }
+}
+
+fn p_drain(proc: &mut PtyProcess) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.drain()
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.drain())
+    }
+}
+
+fn p_drain_into(proc: &mut PtyProcess, out: &mut Vec<u8>) -> std::io::Result<usize> {
+    #[cfg(feature = "sync")]
+    {
+        proc.drain_into(out)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.drain_into(out))
+    }
+}
+
+fn p_read_until_timeout(
+    proc: &mut PtyProcess,
+    delim: u8,
+    timeout: Option<Duration>,
+) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_until_timeout(delim, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_until_timeout(delim, timeout))
+    }
+}
+
+fn p_read_until_into(
+    proc: &mut PtyProcess,
+    delim: u8,
+    out: &mut Vec<u8>,
+    timeout: Option<Duration>,
+) -> std::io::Result<usize> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_until_into(delim, out, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_until_into(delim, out, timeout))
+    }
+}
+
+fn p_read_exact_timeout(
+    proc: &mut PtyProcess,
+    buf: &mut [u8],
+    timeout: Duration,
+) -> std::io::Result<()> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_exact_timeout(buf, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_exact_timeout(buf, timeout))
+    }
+}
+
+fn p_expect_silence(proc: &mut PtyProcess, duration: Duration) -> std::io::Result<bool> {
+    #[cfg(feature = "sync")]
+    {
+        proc.expect_silence(duration)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.expect_silence(duration))
+    }
+}
+
+fn p_read_until_match(
+    proc: &mut PtyProcess,
+    needle: &[u8],
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<Vec<u8>>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.read_until_match(needle, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.read_until_match(needle, timeout))
+    }
+}
+
+fn p_wait_for_any(
+    proc: &mut PtyProcess,
+    needles: &[&[u8]],
+    timeout: Option<Duration>,
+) -> std::io::Result<Option<(usize, Vec<u8>)>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.wait_for_any(needles, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.wait_for_any(needles, timeout))
+    }
+}
+
+fn p_send_line_expect(
+    proc: &mut PtyProcess,
+    line: &str,
+    prompt: &[u8],
+    timeout: Option<Duration>,
+) -> std::io::Result<Vec<u8>> {
+    #[cfg(feature = "sync")]
+    {
+        proc.send_line_expect(line, prompt, timeout)
+    }
+    #[cfg(feature = "async")]
+    {
+        block_on(proc.send_line_expect(line, prompt, timeout))
+    }
+}
+
 fn p_interact(proc: &mut PtyProcess) -> std::io::Result<WaitStatus> {
     #[cfg(feature = "sync")]
     {
@@ -565,3 +1918,59 @@ fn p_interact(proc: &mut PtyProcess) -> std::io::Result<WaitStatus> {
         block_on(proc.interact())
     }
 }
+
+#[cfg(feature = "sync")]
+fn p_interact_io(
+    proc: &mut PtyProcess,
+    input: impl std::io::Read,
+    output: impl std::io::Write,
+) -> std::io::Result<WaitStatus> {
+    proc.interact_io(input, output)
+}
+
+#[cfg(feature = "async")]
+fn p_interact_io(
+    proc: &mut PtyProcess,
+    input: impl futures_lite::AsyncRead + Unpin,
+    output: impl futures_lite::AsyncWrite + Unpin,
+) -> std::io::Result<WaitStatus> {
+    block_on(proc.interact_io(input, output))
+}
+
+#[cfg(feature = "sync")]
+fn p_interact_io_with_escape(
+    proc: &mut PtyProcess,
+    input: impl std::io::Read,
+    output: impl std::io::Write,
+    escape: ControlCode,
+) -> std::io::Result<WaitStatus> {
+    proc.interact_io_with_escape(input, output, escape)
+}
+
+#[cfg(feature = "async")]
+fn p_interact_io_with_escape(
+    proc: &mut PtyProcess,
+    input: impl futures_lite::AsyncRead + Unpin,
+    output: impl futures_lite::AsyncWrite + Unpin,
+    escape: ControlCode,
+) -> std::io::Result<WaitStatus> {
+    block_on(proc.interact_io_with_escape(input, output, escape))
+}
+
+fn wait_stopped(proc: &PtyProcess) {
+    let now = std::time::Instant::now();
+    while !proc.is_stopped().unwrap() {
+        assert!(now.elapsed() < Duration::from_secs(5));
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+/// Waits until there's something to read from a PTY without reading it.
+fn wait_pty_readable(proc: &PtyProcess) {
+    use nix::poll::{poll, PollFd, PollFlags};
+    use std::os::unix::io::AsRawFd;
+
+    let pty = proc.get_pty_handle().unwrap();
+    let mut fds = [PollFd::new(pty.as_raw_fd(), PollFlags::POLLIN)];
+    assert_eq!(poll(&mut fds, 5000).unwrap(), 1);
+}
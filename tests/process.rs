@@ -0,0 +1,445 @@
+use ptyprocess::{PtyError, PtyProcess, Signal, WaitStatus};
+use std::{process::Command, thread, time::Duration};
+
+#[test]
+fn wait_timeout() {
+    let mut command = Command::new("sleep");
+    command.arg("5");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    assert_eq!(proc.wait_timeout(Duration::from_millis(300)).unwrap(), None);
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn wait_timeout_exited() {
+    let proc = PtyProcess::spawn(Command::new("true")).unwrap();
+
+    assert_eq!(
+        proc.wait_timeout(Duration::from_secs(5)).unwrap(),
+        Some(WaitStatus::Exited(proc.pid(), 0))
+    );
+}
+
+#[test]
+fn wait_exit_code() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "exit 3"]);
+    let proc = PtyProcess::spawn(command).unwrap();
+
+    assert_eq!(proc.wait_exit_code().unwrap(), 3);
+}
+
+#[test]
+fn wait_exit_code_signaled() {
+    let mut command = Command::new("sleep");
+    command.arg("5");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    proc.kill(Signal::SIGTERM).unwrap();
+
+    assert_eq!(proc.wait_exit_code().unwrap(), 128 + Signal::SIGTERM as i32);
+}
+
+#[test]
+fn kill_group() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "sleep 5 | sleep 5"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    // give the shell a time to start a pipeline
+    thread::sleep(Duration::from_millis(300));
+
+    proc.kill_group(Signal::SIGKILL).unwrap();
+
+    assert_eq!(
+        proc.wait().unwrap(),
+        WaitStatus::Signaled(proc.pid(), Signal::SIGKILL, false)
+    );
+}
+
+#[test]
+fn try_wait() {
+    let mut command = Command::new("sleep");
+    command.arg("0.3");
+    let proc = PtyProcess::spawn(command).unwrap();
+
+    assert_eq!(proc.try_wait().unwrap(), None);
+
+    thread::sleep(Duration::from_millis(600));
+
+    let status = WaitStatus::Exited(proc.pid(), 0);
+    assert_eq!(proc.try_wait().unwrap(), Some(status));
+    assert_eq!(proc.try_wait().unwrap(), Some(status));
+}
+
+#[test]
+fn exit_with() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert!(proc.exit_with(&[Signal::SIGTERM], false).unwrap());
+    assert!(!proc.is_alive().unwrap());
+}
+
+#[test]
+fn exit_with_ignored_signal() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "trap '' TERM; while :; do sleep 0.1; done"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+
+    // give the shell a time to set a trap
+    thread::sleep(Duration::from_millis(300));
+
+    assert!(!proc.exit_with(&[Signal::SIGTERM], false).unwrap());
+    assert!(proc.exit_with(&[Signal::SIGTERM], true).unwrap());
+}
+
+#[test]
+fn detach() {
+    let mut command = Command::new("sh");
+    command.args(["-c", "trap '' HUP; sleep 5"]);
+    let proc = PtyProcess::spawn(command).unwrap();
+    let pid = proc.pid().to_string();
+
+    // give the shell a time to set a trap
+    thread::sleep(Duration::from_millis(300));
+
+    proc.detach();
+
+    // give the kernel a time to deliver SIGHUP
+    thread::sleep(Duration::from_millis(300));
+
+    // a terminated child is not reaped so it must be checked it's not a zombie
+    let output = Command::new("ps")
+        .args(["-o", "stat=", "-p", &pid])
+        .output()
+        .unwrap();
+    let stat = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        !stat.trim().is_empty() && !stat.trim().starts_with('Z'),
+        "{:?}",
+        stat
+    );
+
+    Command::new("kill").args(["-9", &pid]).status().unwrap();
+}
+
+#[test]
+fn into_owned_fd() {
+    use std::{fs::File, io::Write};
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let pid = proc.pid();
+
+    let fd = proc.into_owned_fd().unwrap();
+
+    // the child is not terminated
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(
+        nix::sys::wait::waitpid(pid, Some(nix::sys::wait::WaitPidFlag::WNOHANG)).unwrap(),
+        WaitStatus::StillAlive
+    );
+
+    let mut file = File::from(fd);
+    file.write_all(b"hello cat\n").unwrap();
+
+    // closing the last master descriptor hangs up the child
+    drop(file);
+    assert!(matches!(
+        nix::sys::wait::waitpid(pid, None).unwrap(),
+        WaitStatus::Signaled(_, Signal::SIGHUP, _) | WaitStatus::Exited(_, _)
+    ));
+}
+
+#[test]
+fn is_alive_nowait() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert!(proc.is_alive_nowait().unwrap());
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "exit 3"]);
+    let proc = PtyProcess::spawn(command).unwrap();
+
+    thread::sleep(Duration::from_millis(300));
+
+    assert!(!proc.is_alive_nowait().unwrap());
+    assert!(!proc.is_alive_nowait().unwrap());
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 3));
+}
+
+#[test]
+fn group_and_session_id() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert_eq!(proc.group_id().unwrap(), proc.pid());
+    assert_eq!(proc.session_id().unwrap(), proc.pid());
+
+    assert!(proc.exit(true).unwrap());
+    assert!(matches!(proc.group_id(), Err(PtyError::Terminated)));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn foreground_process_group() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert_eq!(proc.foreground_process_group().unwrap(), proc.pid());
+
+    // a shell with job control runs a command in its own group
+    let mut command = Command::new("sh");
+    command.args(["-c", "set -m; sleep 10"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    thread::sleep(Duration::from_millis(300));
+    let pgid = proc.foreground_process_group().unwrap();
+    assert_ne!(pgid, proc.pid());
+    assert!(proc.exit(true).unwrap());
+
+    let proc = PtyProcess::spawn_no_ctty(Command::new("cat")).unwrap();
+    match proc.foreground_process_group() {
+        Err(PtyError::Io(err)) => assert_eq!(err.kind(), std::io::ErrorKind::NotFound),
+        result => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn interrupt_foreground() {
+    // a shell with job control runs a command in its own foreground group,
+    // so only the command is interrupted
+    let mut command = Command::new("sh");
+    command.args([
+        "-c",
+        "set -m; sh -c 'trap \"kill $!; exit 7\" INT; sleep 10 & wait'; exit $?",
+    ]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    thread::sleep(Duration::from_millis(300));
+
+    proc.interrupt_foreground().unwrap();
+    assert_eq!(
+        proc.wait_timeout(Duration::from_secs(5)).unwrap(),
+        Some(WaitStatus::Exited(proc.pid(), 7))
+    );
+
+    let mut proc = PtyProcess::spawn_no_ctty(Command::new("cat")).unwrap();
+    assert!(proc.interrupt_foreground().is_err());
+    assert!(proc.is_alive().unwrap());
+}
+
+#[test]
+fn duplicate() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert!(proc.owns_child());
+
+    let handle = proc.duplicate().unwrap();
+    assert!(!handle.owns_child());
+    assert_eq!(handle.pid(), proc.pid());
+    assert_eq!(handle.get_window_size().unwrap(), (80, 24));
+
+    // a duplicate doesn't terminate a child
+    drop(handle);
+    assert!(proc.is_alive().unwrap());
+
+    // the owner does, while a duplicate still sees a shared status
+    let handle = proc.duplicate().unwrap();
+    let pid = proc.pid();
+    assert!(proc.exit(true).unwrap());
+    drop(proc);
+    assert!(!handle.is_alive().unwrap());
+    assert!(matches!(
+        handle.wait().unwrap(),
+        WaitStatus::Signaled(p, _, _) if p == pid
+    ));
+}
+
+#[test]
+fn wait_twice() {
+    let mut proc = PtyProcess::spawn(Command::new("true")).unwrap();
+    assert!(!proc.is_reaped());
+
+    let status = WaitStatus::Exited(proc.pid(), 0);
+    assert_eq!(proc.wait().unwrap(), status);
+    assert!(proc.is_reaped());
+    assert_eq!(proc.wait().unwrap(), status);
+    assert_eq!(proc.status().unwrap(), status);
+    assert_eq!(proc.try_wait().unwrap(), Some(status));
+    assert!(!proc.is_alive().unwrap());
+
+    // a pid of a reaped child may be reused, so it's not signaled anymore
+    assert!(matches!(
+        proc.kill(Signal::SIGTERM),
+        Err(PtyError::Terminated)
+    ));
+    assert!(matches!(
+        proc.kill_group(Signal::SIGTERM),
+        Err(PtyError::Terminated)
+    ));
+    assert!(matches!(proc.resume(), Err(PtyError::Terminated)));
+}
+
+#[test]
+fn kill_and_wait() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let status = proc
+        .kill_and_wait(Signal::SIGTERM, Duration::from_secs(1))
+        .unwrap();
+    assert_eq!(
+        status,
+        WaitStatus::Signaled(proc.pid(), Signal::SIGTERM, false)
+    );
+    assert_eq!(proc.wait().unwrap(), status);
+
+    // a reaped child is not signaled again
+    assert_eq!(
+        proc.kill_and_wait(Signal::SIGKILL, Duration::from_secs(1))
+            .unwrap(),
+        status
+    );
+
+    // SIGTERM is ignored, so the child is killed
+    let mut command = Command::new("sh");
+    command.args(["-c", "trap '' TERM; sleep 10"]);
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    // give a shell a time to set the trap
+    thread::sleep(Duration::from_millis(300));
+
+    let status = proc
+        .kill_and_wait(Signal::SIGTERM, Duration::from_millis(300))
+        .unwrap();
+    assert_eq!(
+        status,
+        WaitStatus::Signaled(proc.pid(), Signal::SIGKILL, false)
+    );
+}
+
+#[test]
+fn process_group() {
+    use nix::unistd::{getsid, Pid};
+    use ptyprocess::{PtyProcessBuilder, SpawnStage};
+
+    let spawn = |pgid| {
+        PtyProcessBuilder::new()
+            .command(Command::new("cat"))
+            .process_group(pgid)
+            .build()
+            .unwrap()
+    };
+
+    let mut proc = spawn(Pid::from_raw(0));
+    assert_eq!(proc.group_id().unwrap(), proc.pid());
+    assert_eq!(proc.session_id().unwrap(), getsid(None).unwrap());
+    assert!(proc.exit(true).unwrap());
+
+    let mut leader = spawn(Pid::from_raw(0));
+    let mut proc = spawn(leader.pid());
+    assert_eq!(proc.group_id().unwrap(), leader.pid());
+    assert!(proc.exit(true).unwrap());
+    assert!(leader.exit(true).unwrap());
+
+    // a group from another session can't be joined
+    let err = PtyProcessBuilder::new()
+        .command(Command::new("cat"))
+        .process_group(Pid::from_raw(1))
+        .build()
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        PtyError::ChildSetup(SpawnStage::ProcessGroup, _)
+    ));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn wait_async() {
+    let mut command = Command::new("sleep");
+    command.arg("1");
+    let proc = PtyProcess::spawn(command).unwrap();
+
+    let status = futures_lite::future::block_on(proc.wait_async()).unwrap();
+    assert_eq!(status, WaitStatus::Exited(proc.pid(), 0));
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn shutdown() {
+    use nix::sys::{signal::kill, wait::waitpid};
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let pid = proc.pid();
+    assert!(futures_lite::future::block_on(proc.shutdown(false)).unwrap());
+    // the child is already reaped
+    assert!(waitpid(pid, None).is_err());
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "trap '' HUP INT TERM; while true; do sleep 1; done"]);
+    let proc = PtyProcess::spawn(command).unwrap();
+    let pid = proc.pid();
+    // let a shell set its traps
+    thread::sleep(Duration::from_millis(300));
+
+    assert!(!futures_lite::future::block_on(proc.shutdown(false)).unwrap());
+    // the child is left running
+    kill(pid, Signal::SIGKILL).unwrap();
+    assert_eq!(
+        waitpid(pid, None).unwrap(),
+        WaitStatus::Signaled(pid, Signal::SIGKILL, false)
+    );
+}
+
+#[test]
+fn pty_spawn_into() {
+    use ptyprocess::Pty;
+    use std::os::unix::{fs::OpenOptionsExt, io::AsRawFd};
+
+    let pty = Pty::open().unwrap();
+    let slave_name = pty.slave_name().unwrap();
+    assert!(slave_name.starts_with("/dev/"));
+    assert!(nix::unistd::isatty(pty.master_fd().as_raw_fd()).unwrap());
+
+    // the slave is already unlocked
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .custom_flags(nix::libc::O_NOCTTY)
+        .open(&slave_name)
+        .unwrap();
+
+    let mut proc = pty.spawn_into(Command::new("cat")).unwrap();
+    assert_eq!(proc.slave_name().unwrap(), slave_name);
+    assert!(proc.is_alive().unwrap());
+    assert!(proc.exit(true).unwrap());
+}
+
+#[test]
+fn stop_and_resume() {
+    let wait_for = |check: &dyn Fn() -> bool| {
+        let now = std::time::Instant::now();
+        while !check() {
+            assert!(now.elapsed() < Duration::from_secs(5));
+            thread::sleep(Duration::from_millis(10));
+        }
+    };
+
+    let mut command = Command::new("sleep");
+    command.arg("10");
+    let mut proc = PtyProcess::spawn(command).unwrap();
+    assert!(!proc.is_stopped().unwrap());
+
+    // SIGSTOP is used as SIGTSTP is ignored by a child in an orphaned process group
+    proc.kill(Signal::SIGSTOP).unwrap();
+    wait_for(&|| proc.is_stopped().unwrap());
+    // a stop isn't consumed by a check
+    assert!(proc.is_stopped().unwrap());
+    assert!(proc.is_alive().unwrap());
+
+    proc.resume().unwrap();
+    assert!(!proc.is_stopped().unwrap());
+    assert!(proc.is_alive().unwrap());
+
+    // a stop consumed by status_untraced is not reported by is_stopped
+    proc.kill(Signal::SIGSTOP).unwrap();
+    wait_for(&|| {
+        proc.status_untraced().unwrap() == WaitStatus::Stopped(proc.pid(), Signal::SIGSTOP)
+    });
+    assert!(!proc.is_stopped().unwrap());
+
+    proc.resume().unwrap();
+    assert!(proc.exit(true).unwrap());
+}
@@ -1,5 +1,9 @@
-use ptyprocess::PtyProcess;
-use std::{process::Command, time::Duration};
+use ptyprocess::{termios::LocalFlags, PtyProcess, PtyProcessBuilder};
+use std::{
+    os::unix::io::{AsFd, AsRawFd},
+    process::Command,
+    time::Duration,
+};
 
 #[test]
 fn default_win_size() {
@@ -8,6 +12,22 @@ fn default_win_size() {
     assert_eq!(proc.get_window_size().unwrap(), (80, 24));
 }
 
+#[cfg(feature = "sync")]
+#[test]
+fn spawn_with_win_size() {
+    use std::io::Read;
+
+    let mut command = Command::new("stty");
+    command.arg("size");
+    let mut proc = PtyProcess::spawn_with_size(command, 120, 40).unwrap();
+
+    assert_eq!(proc.get_window_size().unwrap(), (120, 40));
+
+    let mut buf = String::new();
+    proc.read_to_string(&mut buf).unwrap();
+    assert_eq!(buf, "40 120\r\n");
+}
+
 #[test]
 fn set_win_size() {
     let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -17,6 +37,57 @@ fn set_win_size() {
     assert_eq!(proc.get_window_size().unwrap(), (100, 200));
 }
 
+#[test]
+fn set_win_size_checked() {
+    use ptyprocess::PtyError;
+
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.set_window_size_checked(100, 200).unwrap();
+    assert_eq!(proc.get_window_size().unwrap(), (100, 200));
+
+    for (cols, rows) in [(0, 200), (100, 0), (0, 0)] {
+        let err = proc.set_window_size_checked(cols, rows).unwrap_err();
+        assert!(matches!(err, PtyError::Winsize(_)), "{:?}", err);
+        assert_eq!(err.raw_os_error(), Some(nix::libc::EINVAL));
+    }
+    assert_eq!(proc.get_window_size().unwrap(), (100, 200));
+}
+
+#[test]
+fn set_win_size_pixels() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert_eq!(proc.get_window_size_pixels().unwrap(), (80, 24, 0, 0));
+
+    proc.set_window_size_pixels(100, 50, 800, 600).unwrap();
+
+    assert_eq!(proc.get_window_size_pixels().unwrap(), (100, 50, 800, 600));
+    assert_eq!(proc.get_window_size().unwrap(), (100, 50));
+}
+
+#[test]
+fn set_win_size_from_terminal() {
+    let mut terminal = PtyProcess::spawn(Command::new("cat")).unwrap();
+    terminal.set_window_size_pixels(100, 50, 800, 600).unwrap();
+
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    proc.set_window_size_from_terminal(terminal.as_raw_fd())
+        .unwrap();
+
+    assert_eq!(proc.get_window_size_pixels().unwrap(), (100, 50, 800, 600));
+}
+
+#[test]
+fn resize() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.resize(100, 200).unwrap();
+
+    assert_eq!(proc.get_window_size().unwrap(), (100, 200));
+    assert!(proc.is_alive().unwrap());
+}
+
 #[test]
 fn default_echo() {
     let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
@@ -35,3 +106,217 @@ fn set_echo() {
 
     assert!(proc.get_echo().unwrap());
 }
+
+#[test]
+fn set_local_flags() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let flags = proc.get_local_flags().unwrap();
+    assert!(flags.contains(LocalFlags::ICANON | LocalFlags::ISIG));
+    assert!(!flags.contains(LocalFlags::ECHO));
+
+    proc.set_local_flags(LocalFlags::ECHO | LocalFlags::ECHONL, true)
+        .unwrap();
+    proc.set_local_flags(LocalFlags::ICANON, false).unwrap();
+
+    let flags = proc.get_local_flags().unwrap();
+    assert!(flags.contains(LocalFlags::ECHO | LocalFlags::ECHONL | LocalFlags::ISIG));
+    assert!(!flags.contains(LocalFlags::ICANON));
+    assert!(proc.get_echo().unwrap());
+}
+
+#[test]
+fn termios_changed_since_spawn() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    assert!(!proc.termios_changed_since_spawn().unwrap());
+
+    proc.set_local_flags(LocalFlags::ICANON, false).unwrap();
+    assert!(proc.termios_changed_since_spawn().unwrap());
+
+    let flags = proc.initial_termios();
+    proc.set_termios(&flags).unwrap();
+    assert!(!proc.termios_changed_since_spawn().unwrap());
+}
+
+#[test]
+fn builder() {
+    let proc = PtyProcessBuilder::new()
+        .command(Command::new("cat"))
+        .window_size(100, 50)
+        .echo(true)
+        .build()
+        .unwrap();
+
+    assert_eq!(proc.get_window_size().unwrap(), (100, 50));
+    assert!(proc.get_echo().unwrap());
+}
+
+#[test]
+fn builder_without_command() {
+    assert!(PtyProcessBuilder::new().build().is_err());
+}
+
+#[test]
+fn spawn_inherit_termios() {
+    use ptyprocess::termios::SpecialCharacterIndices;
+
+    let mut terminal = PtyProcess::spawn(Command::new("cat")).unwrap();
+    terminal.set_echo(true).unwrap();
+    terminal
+        .set_term_char(SpecialCharacterIndices::VERASE, 0x8)
+        .unwrap();
+    let mut flags = terminal.get_termios().unwrap();
+    flags.local_flags.remove(LocalFlags::ICANON);
+    terminal.set_termios(&flags).unwrap();
+
+    let proc =
+        PtyProcess::spawn_inherit_termios(Command::new("cat"), terminal.as_raw_fd()).unwrap();
+
+    let flags = proc.get_termios().unwrap();
+    assert!(!flags.local_flags.contains(LocalFlags::ICANON));
+    assert_eq!(
+        proc.get_term_char(SpecialCharacterIndices::VERASE).unwrap(),
+        0x8
+    );
+    // echo is still turned off
+    assert!(!proc.get_echo().unwrap());
+}
+
+#[test]
+fn set_termios() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let mut flags = proc.get_termios().unwrap();
+    assert!(flags.local_flags.contains(LocalFlags::ICANON));
+
+    flags.local_flags.remove(LocalFlags::ICANON);
+    proc.set_termios(&flags).unwrap();
+
+    let flags = proc.get_termios().unwrap();
+    assert!(!flags.local_flags.contains(LocalFlags::ICANON));
+}
+
+#[test]
+fn set_raw_mode() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.set_raw_mode().unwrap();
+
+    let flags = proc.get_termios().unwrap();
+    assert!(!flags.local_flags.contains(LocalFlags::ICANON));
+    assert!(!flags.local_flags.contains(LocalFlags::ISIG));
+
+    proc.set_canonical_mode().unwrap();
+
+    let flags = proc.get_termios().unwrap();
+    assert!(flags.local_flags.contains(LocalFlags::ICANON));
+    assert!(flags.local_flags.contains(LocalFlags::ISIG));
+    assert!(flags.local_flags.contains(LocalFlags::ECHO));
+}
+
+#[test]
+fn default_control_chars() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    assert_eq!(proc.get_eof_char(), 0x4);
+    assert_eq!(proc.get_intr_char(), 0x3);
+}
+
+#[test]
+fn set_control_chars() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.set_eof_char(0x1);
+    proc.set_intr_char(0x2);
+
+    assert_eq!(proc.get_eof_char(), 0x1);
+    assert_eq!(proc.get_intr_char(), 0x2);
+}
+
+#[test]
+fn as_raw_fd() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let handle = proc.get_pty_handle().unwrap();
+    assert_ne!(proc.as_raw_fd(), handle.as_raw_fd());
+    assert_eq!(proc.as_fd().as_raw_fd(), proc.as_raw_fd());
+}
+
+#[test]
+fn slave_name() {
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let name = proc.slave_name().unwrap();
+    assert!(name.starts_with("/dev/"), "{:?}", name);
+}
+
+#[test]
+fn open_slave() {
+    use std::io::IsTerminal;
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    let slave = proc.open_slave().unwrap();
+    assert!(slave.is_terminal());
+    assert_ne!(slave.as_raw_fd(), proc.as_raw_fd());
+}
+
+#[test]
+fn set_term_char() {
+    use ptyprocess::termios::SpecialCharacterIndices;
+
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.set_term_char(SpecialCharacterIndices::VERASE, 0x8)
+        .unwrap();
+    assert_eq!(
+        proc.get_term_char(SpecialCharacterIndices::VERASE).unwrap(),
+        0x8
+    );
+
+    proc.set_term_char(SpecialCharacterIndices::VEOF, 0x1)
+        .unwrap();
+    assert_eq!(
+        proc.get_term_char(SpecialCharacterIndices::VEOF).unwrap(),
+        0x1
+    );
+    assert_eq!(proc.get_eof_char(), 0x1);
+}
+
+#[test]
+fn raw_mode_guard() {
+    use ptyprocess::RawModeGuard;
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let terminal = proc.open_slave().unwrap();
+
+    let guard = RawModeGuard::new(terminal.as_raw_fd()).unwrap();
+    assert!(guard.termios().local_flags.contains(LocalFlags::ICANON));
+    assert!(!proc
+        .get_termios()
+        .unwrap()
+        .local_flags
+        .contains(LocalFlags::ICANON));
+    drop(guard);
+    assert!(proc
+        .get_termios()
+        .unwrap()
+        .local_flags
+        .contains(LocalFlags::ICANON));
+
+    let guard = RawModeGuard::new(terminal.as_raw_fd()).unwrap();
+    assert!(!proc
+        .get_termios()
+        .unwrap()
+        .local_flags
+        .contains(LocalFlags::ICANON));
+    guard.restore().unwrap();
+    assert!(proc
+        .get_termios()
+        .unwrap()
+        .local_flags
+        .contains(LocalFlags::ICANON));
+
+    let file = std::fs::File::open("/dev/null").unwrap();
+    assert!(RawModeGuard::new(file.as_raw_fd()).is_err());
+}
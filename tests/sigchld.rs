@@ -0,0 +1,57 @@
+#![cfg(feature = "sigchld")]
+
+use nix::poll::{poll, PollFd, PollFlags};
+use nix::sys::wait::waitpid;
+use ptyprocess::{PtyError, PtyProcess, SigchldReaper, WaitStatus};
+use std::{io, os::unix::io::AsRawFd, process::Command};
+
+// A handler is installed for a whole process, so everything is checked by a single test.
+#[test]
+fn sigchld_reaper() {
+    let mut reaper = SigchldReaper::install().unwrap();
+    assert!(reaper.poll_exited().is_empty());
+
+    match SigchldReaper::install().unwrap_err() {
+        PtyError::Io(err) => assert_eq!(err.kind(), io::ErrorKind::AlreadyExists),
+        err => panic!("unexpected error {:?}", err),
+    }
+
+    let mut command = Command::new("sh");
+    command.args(["-c", "exit 3"]);
+    let proc = PtyProcess::spawn(command).unwrap();
+    let mut cat = PtyProcess::spawn(Command::new("cat")).unwrap();
+    reaper.watch(&proc);
+    reaper.watch(&cat);
+    assert_eq!(reaper.watched(), 2);
+
+    let mut exited = Vec::new();
+    while exited.is_empty() {
+        let mut fds = [PollFd::new(reaper.as_raw_fd(), PollFlags::POLLIN)];
+        // it's interrupted if a signal is delivered to this thread
+        let _ = poll(&mut fds, 5000);
+        exited = reaper.poll_exited();
+    }
+
+    assert_eq!(exited, [(proc.pid(), WaitStatus::Exited(proc.pid(), 3))]);
+    assert_eq!(reaper.watched(), 1);
+    // the status is known to the handle
+    assert_eq!(proc.wait().unwrap(), WaitStatus::Exited(proc.pid(), 3));
+
+    // a child reaped by its handle is reported as well
+    assert!(cat.exit(true).unwrap());
+    let exited = reaper.poll_exited();
+    assert_eq!(exited.len(), 1);
+    assert_eq!(exited[0].0, cat.pid());
+    assert_eq!(reaper.watched(), 0);
+
+    // a child reaped by someone else can't be waited for, but it's not lost
+    let lost = PtyProcess::spawn(Command::new("true")).unwrap();
+    reaper.watch(&lost);
+    waitpid(lost.pid(), None).unwrap();
+    assert!(reaper.poll_exited().is_empty());
+    assert!(reaper.poll_exited().is_empty());
+    assert_eq!(reaper.watched(), 1);
+
+    drop(reaper);
+    SigchldReaper::install().unwrap();
+}
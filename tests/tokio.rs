@@ -0,0 +1,29 @@
+#![cfg(feature = "tokio")]
+
+use ptyprocess::PtyProcess;
+use std::process::Command;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+#[tokio::test]
+async fn read_write() {
+    let mut proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+
+    proc.write_all(b"hello cat\n").await.unwrap();
+
+    let mut buf = vec![0; 128];
+    let n = proc.read(&mut buf).await.unwrap();
+    assert_eq!(&buf[..n], b"hello cat\r\n");
+
+    assert!(proc.exit(true).unwrap());
+}
+
+#[tokio::test]
+async fn read_to_end() {
+    let mut cmd = Command::new("echo");
+    cmd.arg("Hello World");
+    let mut proc = PtyProcess::spawn(cmd).unwrap();
+
+    let mut buf = Vec::new();
+    proc.read_to_end(&mut buf).await.unwrap();
+    assert_eq!(buf, b"Hello World\r\n");
+}
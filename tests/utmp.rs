@@ -0,0 +1,58 @@
+#![cfg(all(feature = "utmp", target_os = "linux", target_env = "gnu"))]
+
+use nix::libc;
+use ptyprocess::PtyProcess;
+use std::{ffi::CString, process::Command};
+
+/// Returns types of records of a given process.
+fn records(pid: i32) -> Vec<(libc::c_short, String)> {
+    let mut records = Vec::new();
+    unsafe {
+        libc::setutxent();
+        loop {
+            let record = libc::getutxent();
+            if record.is_null() {
+                break;
+            }
+
+            let record = &*record;
+            if record.ut_pid == pid {
+                let user = record
+                    .ut_user
+                    .iter()
+                    .take_while(|&&c| c != 0)
+                    .map(|&c| c as u8 as char)
+                    .collect();
+                records.push((record.ut_type, user));
+            }
+        }
+        libc::endutxent();
+    }
+
+    records
+}
+
+#[test]
+fn register_utmp() {
+    // use a separate file as a system one may be not writable
+    let file = std::env::temp_dir().join(format!("ptyprocess-utmp-{}", std::process::id()));
+    std::fs::File::create(&file).unwrap();
+    let path = CString::new(file.to_str().unwrap()).unwrap();
+    assert_eq!(unsafe { libc::utmpxname(path.as_ptr()) }, 0);
+
+    let proc = PtyProcess::spawn(Command::new("cat")).unwrap();
+    let pid = proc.pid().as_raw();
+
+    proc.register_utmp("ptyprocess").unwrap();
+    assert_eq!(
+        records(pid),
+        vec![(libc::USER_PROCESS, "ptyprocess".to_owned())]
+    );
+
+    drop(proc);
+
+    // the record is reused
+    assert_eq!(records(pid), vec![(libc::DEAD_PROCESS, String::new())]);
+
+    std::fs::remove_file(file).unwrap();
+}